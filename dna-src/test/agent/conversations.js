@@ -0,0 +1,385 @@
+module.exports = scenario => {
+
+  const testNewChannelParams = {
+    name: 'test new conversation',
+    description: 'for testing...',
+  }
+
+  const { config1 } = require('../config')
+
+  scenario('Can list the conversations an agent has joined', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    await s.consistency()
+
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+
+    const my_conversations = await player1.call('chat', 'chat', 'get_my_conversations', {})
+    t.deepEqual(my_conversations.Ok.length, 1, 'creator is a member of the new conversation')
+    t.deepEqual(my_conversations.Ok[0].address, conversation_addr)
+    t.deepEqual(my_conversations.Ok[0].entry.name, testNewChannelParams.name)
+
+    const player2_before = await player2.call('chat', 'chat', 'get_my_conversations', {})
+    t.deepEqual(player2_before.Ok.length, 0, 'player2 has not joined anything yet')
+
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+    await s.consistency()
+
+    const player2_as_seen_by_player1 = await player1.call('chat', 'chat', 'get_member_conversations', {agent_address: player2.instance('chat').agentAddress})
+    t.deepEqual(player2_as_seen_by_player1.Ok.length, 1, 'other agents can see what player2 joined')
+  })
+
+  scenario('Moderators can pin messages and they are returned in pin order', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+
+    const message = {timestamp: 0, message_type: 'text', meta: '{}'}
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, payload: 'first'}})
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, payload: 'second'}})
+    await s.consistency()
+    const messages = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok
+    const first = messages.find(m => m.entry.payload === 'first').address
+    const second = messages.find(m => m.entry.payload === 'second').address
+
+    const not_moderator = await player2.call('chat', 'chat', 'pin_message', {conversation_address: conversation_addr, message_address: first})
+    t.notEqual(not_moderator.Err, undefined, 'only moderators may pin')
+
+    await player1.call('chat', 'chat', 'pin_message', {conversation_address: conversation_addr, message_address: second})
+    await player1.call('chat', 'chat', 'pin_message', {conversation_address: conversation_addr, message_address: first})
+    await s.consistency()
+    const pinned = await player2.call('chat', 'chat', 'get_pinned_messages', {conversation_address: conversation_addr})
+    t.deepEqual(pinned.Ok.map(p => p.entry.payload), ['second', 'first'])
+
+    await player1.call('chat', 'chat', 'unpin_message', {conversation_address: conversation_addr, message_address: second})
+    await s.consistency()
+    const after_unpin = await player2.call('chat', 'chat', 'get_pinned_messages', {conversation_address: conversation_addr})
+    t.deepEqual(after_unpin.Ok.map(p => p.entry.payload), ['first'])
+  })
+
+  scenario('Moderators can resolve reports by banning the author', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+
+    const message = {timestamp: 0, message_type: 'text', meta: '{}'}
+    await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, payload: 'spam'}})
+    await s.consistency()
+    const spam = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok[0].address
+
+    const other = (await player1.call('chat', 'chat', 'start_conversation', {...testNewChannelParams, name: 'elsewhere'})).Ok
+    await s.consistency()
+    const misfiled = await player1.call('chat', 'chat', 'file_report', {conversation_address: other, subject: {message: spam}, reason: 'spam', note: ''})
+    t.notEqual(misfiled.Err, undefined, 'reported messages must be in the reported conversation')
+
+    const report_result = await player1.call('chat', 'chat', 'file_report', {conversation_address: conversation_addr, subject: {message: spam}, reason: 'spam', note: ''})
+    await s.consistency()
+
+    const not_moderator = await player2.call('chat', 'chat', 'get_reports', {conversation_address: conversation_addr})
+    t.notEqual(not_moderator.Err, undefined, 'only moderators see the queue')
+
+    const queue = await player1.call('chat', 'chat', 'get_reports', {conversation_address: conversation_addr})
+    t.deepEqual(queue.Ok.length, 1)
+    t.deepEqual(queue.Ok[0].resolution, null)
+    t.ok(queue.Ok[0].filed_at > 0, 'filing time comes from the header')
+
+    await player1.call('chat', 'chat', 'resolve_report', {report_address: report_result.Ok, action: 'ban_author', note: 'repeat offender'})
+    await s.consistency()
+    const resolved = await player1.call('chat', 'chat', 'get_reports', {conversation_address: conversation_addr})
+    t.deepEqual(resolved.Ok[0].resolution.action, 'ban_author')
+
+    const banned_post = await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, payload: 'more spam'}})
+    t.notEqual(banned_post.Err, undefined, 'banned agents cannot post')
+
+    const members = (await player1.call('chat', 'chat', 'get_members', {conversation_address: conversation_addr})).Ok
+    t.deepEqual(members.includes(player2.instance('chat').agentAddress), false, 'banning removes the member')
+    const messages = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok
+    t.deepEqual(messages.map(m => m.address), [spam], 'messages posted before the ban stay valid')
+  })
+
+  scenario('Moderation actions are recorded in the audit log', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+    await s.consistency()
+    const player2_addr = player2.instance('chat').agentAddress
+
+    await player1.call('chat', 'chat', 'set_conversation_topic', {conversation_address: conversation_addr, description: 'new topic'})
+    await s.consistency()
+    await player1.call('chat', 'chat', 'kick_member', {conversation_address: conversation_addr, agent_address: player2_addr})
+    await s.consistency()
+
+    const not_moderator = await player2.call('chat', 'chat', 'kick_member', {conversation_address: conversation_addr, agent_address: player2_addr})
+    t.notEqual(not_moderator.Err, undefined, 'only moderators may kick')
+
+    const members = await player1.call('chat', 'chat', 'get_members', {conversation_address: conversation_addr})
+    t.notOk(members.Ok.includes(player2_addr), 'kicked agent is no longer a member')
+
+    const log = await player2.call('chat', 'chat', 'get_audit_log', {conversation_address: conversation_addr, page: 0})
+    t.deepEqual(log.Ok.map(e => e.entry.action), [{kick: player2_addr}, {change_topic: 'new topic'}], 'newest first')
+    t.deepEqual(log.Ok[0].entry.actor, player1.instance('chat').agentAddress)
+
+    const next_page = await player2.call('chat', 'chat', 'get_audit_log', {conversation_address: conversation_addr, page: 1})
+    t.deepEqual(next_page.Ok.length, 0)
+  })
+
+  scenario('Posting policies limit who can post and how often', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+
+    const message = {message_type: 'text', meta: '{}'}
+    await player1.call('chat', 'chat', 'set_posting_policy', {conversation_address: conversation_addr, posting_policy: {announce: true}})
+    await s.consistency()
+    const announcement = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, timestamp: 0, payload: 'announcement'}})
+    t.deepEqual(announcement.Err, undefined, 'moderators can post announcements')
+    const reply = await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, timestamp: 0, payload: 'reply'}})
+    t.notEqual(reply.Err, undefined, 'members cannot post in announce mode')
+
+    // slow mode goes by when messages are committed, so message timestamps are the real time here
+    await player1.call('chat', 'chat', 'set_posting_policy', {conversation_address: conversation_addr, posting_policy: {slow_mode: 2000}})
+    await s.consistency()
+    const first = await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, timestamp: Date.now(), payload: 'first'}})
+    t.deepEqual(first.Err, undefined)
+    await s.consistency()
+    const too_soon = await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, timestamp: Date.now(), payload: 'too soon'}})
+    t.notEqual(too_soon.Err, undefined, 'members must wait out the slow mode interval')
+    const backdated = await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, timestamp: Date.now() - 10000, payload: 'backdated'}})
+    t.notEqual(backdated.Err, undefined, 'backdating a message does not get around slow mode')
+    await new Promise(resolve => setTimeout(resolve, 2500))
+    const later = await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...message, timestamp: Date.now(), payload: 'later'}})
+    t.deepEqual(later.Err, undefined)
+  })
+
+  scenario('Invite only conversations can be joined with a valid invite', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+    await player1.call('chat', 'chat', 'set_join_policy', {conversation_address: conversation_addr, join_policy: 'invite_only'})
+    await s.consistency()
+
+    const uninvited = await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+    t.notEqual(uninvited.Err, undefined, 'an invite is needed')
+
+    const expired = (await player1.call('chat', 'chat', 'create_invite', {conversation_address: conversation_addr, max_uses: 1, expires_at: 10})).Ok
+    const token = (await player1.call('chat', 'chat', 'create_invite', {conversation_address: conversation_addr, max_uses: 1, expires_at: Date.now() + 60000})).Ok
+    await s.consistency()
+
+    const too_late = await player2.call('chat', 'chat', 'redeem_invite', {token: expired, now: Date.now()})
+    t.notEqual(too_late.Err, undefined, 'expired invites cannot be redeemed')
+    const lying_about_time = await player2.call('chat', 'chat', 'redeem_invite', {token: expired, now: 0})
+    t.notEqual(lying_about_time.Err, undefined, 'expiry is checked against the time the agent joined')
+
+    const outstanding = await player1.call('chat', 'chat', 'list_invites', {now: Date.now()})
+    t.deepEqual(outstanding.Ok.map(i => i.token), [token])
+
+    const address_only = await player2.call('chat', 'chat', 'redeem_invite', {token: outstanding.Ok[0].address, now: Date.now()})
+    t.notEqual(address_only.Err, undefined, 'the invite address alone is not enough to redeem it')
+
+    const redeemed = await player2.call('chat', 'chat', 'redeem_invite', {token, now: Date.now()})
+    t.deepEqual(redeemed.Err, undefined)
+    await s.consistency()
+    const members = await player1.call('chat', 'chat', 'get_members', {conversation_address: conversation_addr})
+    t.ok(members.Ok.includes(player2.instance('chat').agentAddress))
+
+    const used_up = await player1.call('chat', 'chat', 'list_invites', {now: Date.now()})
+    t.deepEqual(used_up.Ok.length, 0, 'exhausted invites are no longer outstanding')
+  })
+
+  scenario('Agents join gated conversations once a moderator approves their request', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+    await player1.call('chat', 'chat', 'set_join_policy', {conversation_address: conversation_addr, join_policy: 'approval'})
+    await s.consistency()
+
+    const pending = await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+    t.deepEqual(pending.Err, undefined, 'joining makes a request')
+    await s.consistency()
+    const player2_addr = player2.instance('chat').agentAddress
+    const before = await player1.call('chat', 'chat', 'get_members', {conversation_address: conversation_addr})
+    t.notOk(before.Ok.includes(player2_addr), 'not a member until approved')
+
+    const requests = await player1.call('chat', 'chat', 'get_join_requests', {conversation_address: conversation_addr})
+    t.deepEqual(requests.Ok.map(r => r.status), ['pending'])
+    await player1.call('chat', 'chat', 'deny_join', {request_address: requests.Ok[0].address})
+    await s.consistency()
+
+    const again = await player2.call('chat', 'chat', 'request_to_join', {conversation_address: conversation_addr})
+    t.notEqual(again.Ok, requests.Ok[0].address, 'a denied agent can ask again')
+    await s.consistency()
+    const both = await player1.call('chat', 'chat', 'get_join_requests', {conversation_address: conversation_addr})
+    t.deepEqual(both.Ok.map(r => r.status).sort(), ['denied', 'pending'])
+    await player1.call('chat', 'chat', 'approve_join', {request_address: again.Ok})
+    await s.consistency()
+
+    const approved = await player1.call('chat', 'chat', 'get_members', {conversation_address: conversation_addr})
+    t.notOk(approved.Ok.includes(player2_addr), 'approval alone does not add the member')
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+    await s.consistency()
+    const after = await player1.call('chat', 'chat', 'get_members', {conversation_address: conversation_addr})
+    t.ok(after.Ok.includes(player2_addr))
+  })
+
+  scenario('Conversations can be grouped into ordered categories', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const engineering = (await player1.call('chat', 'chat', 'start_conversation', {name: 'engineering', description: ''})).Ok
+    const lunch = (await player1.call('chat', 'chat', 'start_conversation', {name: 'lunch', description: ''})).Ok
+    await player1.call('chat', 'chat', 'start_conversation', {name: 'misc', description: ''})
+    await s.consistency()
+
+    const spam = await player2.call('chat', 'chat', 'create_category', {name: 'Spam', position: 0})
+    t.deepEqual(spam.Err, undefined, 'any agent can create a global category')
+
+    const social = (await player1.call('chat', 'chat', 'create_category', {name: 'Social', position: 2})).Ok
+    const work = (await player1.call('chat', 'chat', 'create_category', {name: 'Work', position: 3})).Ok
+    await s.consistency()
+
+    const renamed_by_other = await player2.call('chat', 'chat', 'update_category', {category_address: work, name: 'Mine', position: 0})
+    t.notEqual(renamed_by_other.Err, undefined, 'only moderators of the category can change it')
+    const renamed = await player1.call('chat', 'chat', 'update_category', {category_address: work, name: 'Engineering', position: 1})
+    t.deepEqual(renamed.Err, undefined)
+    await s.consistency()
+
+    const moved_by_other = await player2.call('chat', 'chat', 'move_conversation_to_category', {conversation_address: lunch, category_address: spam.Ok})
+    t.notEqual(moved_by_other.Err, undefined, 'only moderators of a conversation can move it')
+    await player1.call('chat', 'chat', 'move_conversation_to_category', {conversation_address: engineering, category_address: social})
+    await player1.call('chat', 'chat', 'move_conversation_to_category', {conversation_address: lunch, category_address: social})
+    await s.consistency()
+    await player1.call('chat', 'chat', 'move_conversation_to_category', {conversation_address: engineering, category_address: work})
+    await s.consistency()
+
+    const tree = (await player1.call('chat', 'chat', 'get_conversation_tree', {})).Ok
+    t.deepEqual(tree.categories.map(c => c.category.name), ['Spam', 'Engineering', 'Social'])
+    t.deepEqual(tree.categories.map(c => c.conversations.map(conversation => conversation.entry.name)), [[], ['engineering'], ['lunch']])
+    t.deepEqual(tree.uncategorized.map(c => c.entry.name), ['misc'])
+  })
+
+  scenario('Communities keep their own conversations and members', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const community_addr = (await player1.call('chat', 'chat', 'create_community', {name: 'team', description: ''})).Ok
+    await s.consistency()
+
+    const outsider = await player2.call('chat', 'chat', 'start_conversation', {...testNewChannelParams, community: community_addr})
+    t.notEqual(outsider.Err, undefined, 'only members can start conversations in a community')
+
+    await player2.call('chat', 'chat', 'join_community', {community_address: community_addr})
+    const team_conversation = (await player1.call('chat', 'chat', 'start_conversation', {...testNewChannelParams, community: community_addr})).Ok
+    await player1.call('chat', 'chat', 'start_conversation', {name: 'global', description: ''})
+    await s.consistency()
+
+    const members = await player1.call('chat', 'chat', 'get_community_members', {community_address: community_addr})
+    t.deepEqual(members.Ok.length, 2)
+
+    const in_community = await player2.call('chat', 'chat', 'get_all_public_conversations', {community: community_addr})
+    t.deepEqual(in_community.Ok.map(c => c.entry.name), [testNewChannelParams.name])
+    const global = await player2.call('chat', 'chat', 'get_all_public_conversations', {})
+    t.deepEqual(global.Ok.map(c => c.entry.name), ['global'])
+
+    const by_member = await player2.call('chat', 'chat', 'create_category', {name: 'Mine', position: 0, community: community_addr})
+    t.notEqual(by_member.Err, undefined, 'only admins can create categories in a community')
+    const category = await player1.call('chat', 'chat', 'create_category', {name: 'Team', position: 0, community: community_addr})
+    t.deepEqual(category.Err, undefined)
+    await s.consistency()
+    const tree = await player2.call('chat', 'chat', 'get_conversation_tree', {community: community_addr})
+    t.deepEqual(tree.Ok.categories.map(c => c.category.name), ['Team'])
+
+    const mine_in_community = await player1.call('chat', 'chat', 'get_my_conversations', {community: community_addr})
+    t.deepEqual(mine_in_community.Ok.map(c => c.entry.name), [testNewChannelParams.name])
+    const mine_global = await player1.call('chat', 'chat', 'get_my_conversations', {})
+    t.deepEqual(mine_global.Ok.map(c => c.entry.name), ['global'])
+
+    const player1_addr = player1.instance('chat').agentAddress
+    const player2_addr = player2.instance('chat').agentAddress
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: team_conversation})
+    const removed_by_member = await player2.call('chat', 'chat', 'remove_community_member', {community_address: community_addr, agent_address: player1_addr})
+    t.notEqual(removed_by_member.Err, undefined, 'only admins can remove members')
+
+    await player1.call('chat', 'chat', 'add_community_admin', {community_address: community_addr, agent_address: player2_addr})
+    await s.consistency()
+    const removing_admin = await player1.call('chat', 'chat', 'remove_community_member', {community_address: community_addr, agent_address: player2_addr})
+    t.notEqual(removing_admin.Err, undefined, 'admins are demoted before they are removed')
+    const demoted = await player1.call('chat', 'chat', 'remove_community_admin', {community_address: community_addr, agent_address: player2_addr})
+    t.deepEqual(demoted.Err, undefined)
+    await s.consistency()
+    const last_admin = await player1.call('chat', 'chat', 'remove_community_admin', {community_address: community_addr, agent_address: player1_addr})
+    t.notEqual(last_admin.Err, undefined, 'a community keeps at least one admin')
+
+    const removed = await player1.call('chat', 'chat', 'remove_community_member', {community_address: community_addr, agent_address: player2_addr})
+    t.deepEqual(removed.Err, undefined)
+    await s.consistency()
+    const remaining = await player1.call('chat', 'chat', 'get_community_members', {community_address: community_addr})
+    t.deepEqual(remaining.Ok, [player1_addr])
+    const conversation_members = await player1.call('chat', 'chat', 'get_members', {conversation_address: team_conversation, community: community_addr})
+    t.deepEqual(conversation_members.Ok, [player1_addr])
+    const after_removal = await player2.call('chat', 'chat', 'start_conversation', {name: 'rejoin', description: '', community: community_addr})
+    t.notEqual(after_removal.Err, undefined, 'removed members cannot start conversations in the community')
+  })
+
+  scenario('Conversations can be searched and sorted by activity', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    // creation time comes from the headers, so space the conversations out
+    const pause = () => new Promise(resolve => setTimeout(resolve, 1100))
+    const rust = (await player1.call('chat', 'chat', 'start_conversation', {name: 'rust help', description: 'ask anything'})).Ok
+    await pause()
+    const busy = (await player1.call('chat', 'chat', 'start_conversation', {name: 'general', description: 'chat about rust and more'})).Ok
+    await pause()
+    await player1.call('chat', 'chat', 'start_conversation', {name: 'random', description: ''})
+    await player1.call('chat', 'chat', 'set_conversation_tags', {conversation_address: rust, tags: ['#Programming']})
+    await s.consistency()
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: busy})
+    const message = {timestamp: Date.now(), message_type: 'text', meta: '{}', payload: 'hi'}
+    await player2.call('chat', 'chat', 'post_message', {conversation_address: rust, message})
+    await s.consistency()
+    const posted = await player1.call('chat', 'chat', 'get_messages', {address: rust})
+
+    const by_tag = await player1.call('chat', 'chat', 'search_conversations', {query: 'program', sort: 'creation_time', limit: 10})
+    t.deepEqual(by_tag.Ok.map(r => r.conversation.name), ['rust help'])
+
+    const by_members = await player1.call('chat', 'chat', 'search_conversations', {query: 'rust', sort: 'member_count', limit: 10})
+    t.deepEqual(by_members.Ok.map(r => r.conversation.name), ['general', 'rust help'])
+
+    const by_activity = await player1.call('chat', 'chat', 'search_conversations', {query: 'rust', sort: 'recent_activity', limit: 1})
+    t.deepEqual(by_activity.Ok.map(r => r.conversation.name), ['rust help'])
+    t.deepEqual(by_activity.Ok[0].message_count, 1)
+
+    const newest = await player1.call('chat', 'chat', 'search_conversations', {query: '', sort: 'creation_time', limit: 10})
+    t.deepEqual(newest.Ok.map(r => r.conversation.name), ['random', 'general', 'rust help'])
+
+    await player1.call('chat', 'chat', 'delete_message', {conversation_address: rust, message_address: posted.Ok[0].address})
+    await s.consistency()
+    const after_delete = await player1.call('chat', 'chat', 'search_conversations', {query: 'program', sort: 'recent_activity', limit: 1})
+    t.deepEqual(after_delete.Ok[0].message_count, 0, 'deleted messages are no longer counted')
+  })
+}
@@ -91,4 +91,353 @@ module.exports = scenario => {
     const get_message_result = await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})
     t.deepEqual(get_message_result.Ok.length, 0, 'Messages should not be stored.')
   })
+
+  scenario('Mentioned agents can find the messages that mention them', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    await s.consistency()
+
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+
+    const player2_addr = player2.instance('chat').agentAddress
+    const mentionMessage = {...testMessage, payload: 'hey @player2', mentions: [player2_addr]}
+    const post_result = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: mentionMessage})
+    await s.consistency()
+    t.notEqual(post_result.Ok, undefined, 'post should return Ok')
+
+    const mentions_result = await player2.call('chat', 'chat', 'get_my_mentions', {})
+    t.deepEqual(mentions_result.Ok.length, 1)
+    t.deepEqual(mentions_result.Ok[0].conversationAddress, conversation_addr)
+    t.deepEqual(mentions_result.Ok[0].message.payload, mentionMessage.payload)
+
+    const bad_mention = {...testMessage, mentions: [conversation_addr]}
+    const bad_result = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: bad_mention})
+    t.notEqual(bad_result.Err, undefined, 'only agents can be mentioned')
+  })
+
+  scenario('Can search the messages of a conversation with and without an index', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, payload: 'Deploy went fine'}})
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, payload: 'The deploy failed, rolling back'}})
+    await s.consistency()
+
+    const unindexed = await player1.call('chat', 'chat', 'search_messages', {conversation_address: conversation_addr, query: 'DEPLOY failed', limit: 10})
+    t.deepEqual(unindexed.Ok.length, 1, 'every query term must match')
+
+    await player1.call('chat', 'chat', 'update_search_index', {conversation_address: conversation_addr})
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, payload: 'deploy again'}})
+    await s.consistency()
+
+    const indexed = await player1.call('chat', 'chat', 'search_messages', {conversation_address: conversation_addr, query: 'deploy', limit: 10})
+    t.deepEqual(indexed.Ok.length, 3, 'indexed and unindexed messages are both searched')
+
+    const limited = await player1.call('chat', 'chat', 'search_messages', {conversation_address: conversation_addr, query: 'deploy', limit: 1})
+    t.deepEqual(limited.Ok.length, 1)
+  })
+
+  scenario('Messages can be followed by hashtag across conversations', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const first = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    const second = await player1.call('chat', 'chat', 'start_conversation', {...testNewChannelParams, name: 'another conversation'})
+    await s.consistency()
+
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: first.Ok, message: {...testMessage, timestamp: 10, payload: 'Shipping #Release today'}})
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: second.Ok, message: {...testMessage, timestamp: 20, payload: 'notes for the #release and #qa'}})
+    await s.consistency()
+
+    const gated = await player1.call('chat', 'chat', 'start_conversation', {...testNewChannelParams, name: 'invite only'})
+    await s.consistency()
+    await player1.call('chat', 'chat', 'set_join_policy', {conversation_address: gated.Ok, join_policy: 'invite_only'})
+    await s.consistency()
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: gated.Ok, message: {...testMessage, timestamp: 30, payload: 'private #release plans'}})
+    await s.consistency()
+
+    const tagged = await player1.call('chat', 'chat', 'get_messages_by_hashtag', {tag: '#release'})
+    t.deepEqual(tagged.Ok.length, 2, 'tags are case insensitive and span open conversations only')
+
+    const trending = await player1.call('chat', 'chat', 'get_trending_hashtags', {window: {from: 0, to: 100}})
+    t.deepEqual(trending.Ok[0], {tag: 'release', count: 2})
+
+    const later = await player1.call('chat', 'chat', 'get_trending_hashtags', {window: {from: 15, to: 100}})
+    t.deepEqual(later.Ok.length, 2, 'only messages inside the window are counted')
+
+    const removed = tagged.Ok.find(m => m.entry.timestamp === 10)
+    await player1.call('chat', 'chat', 'delete_message', {conversation_address: first.Ok, message_address: removed.address})
+    await s.consistency()
+    const after_delete = await player1.call('chat', 'chat', 'get_messages_by_hashtag', {tag: 'release'})
+    t.deepEqual(after_delete.Ok.map(m => m.entry.timestamp), [20], 'removed messages leave the hashtag')
+  })
+
+  scenario('Can attach an uploaded file to a message', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+
+    const data = Array.from(Buffer.from('line one\nline two\n'))
+    const chunk_result = await player1.call('chat', 'chat', 'upload_attachment_chunk', {data})
+    const upload_result = await player1.call('chat', 'chat', 'upload_attachment', {name: 'log.txt', mime_type: 'text/plain', size: data.length, chunks: [chunk_result.Ok]})
+    await s.consistency()
+    t.notEqual(upload_result.Ok, undefined, 'manifest should be committed')
+
+    const wrong_size = await player1.call('chat', 'chat', 'upload_attachment', {name: 'log.txt', mime_type: 'text/plain', size: data.length + 1, chunks: [chunk_result.Ok]})
+    t.notEqual(wrong_size.Err, undefined, 'chunks must add up to the declared size')
+
+    const post_result = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, attachments: [upload_result.Ok]}})
+    await s.consistency()
+    t.notEqual(post_result.Ok, undefined, 'post should return Ok')
+
+    const manifest = await player1.call('chat', 'chat', 'get_attachment', {address: upload_result.Ok})
+    const chunk = await player1.call('chat', 'chat', 'get_attachment_chunk', {address: manifest.Ok.chunks[0]})
+    t.deepEqual(chunk.Ok.data, data)
+
+    const bad_post = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, attachments: [conversation_addr]}})
+    t.notEqual(bad_post.Err, undefined, 'attachments must exist')
+  })
+
+  scenario('Unknown message types are rejected unless namespaced as extensions', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+
+    const unknown = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, message_type: 'sticker'}})
+    t.notEqual(unknown.Err, undefined, 'unknown types are rejected')
+
+    const extension = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, message_type: 'x-acme/sticker'}})
+    t.notEqual(extension.Ok, undefined, 'extension types are accepted')
+
+    const image_without_attachment = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, message_type: 'image'}})
+    t.notEqual(image_without_attachment.Err, undefined, 'image messages need an image attached')
+  })
+
+  scenario('Agents can vote on polls and change their vote until the poll closes', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+
+    // polls close by the time votes are committed, not a time chosen by the voter
+    const poll = {question: 'Lunch?', options: ['pizza', 'sushi'], closes_at: Date.now() + 5000}
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, message_type: 'poll', payload: JSON.stringify(poll)}})
+    await s.consistency()
+    const poll_addr = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok[0].address
+
+    await player1.call('chat', 'chat', 'vote', {conversation_address: conversation_addr, message_address: poll_addr, option: 0})
+    await player2.call('chat', 'chat', 'vote', {conversation_address: conversation_addr, message_address: poll_addr, option: 0})
+    await player2.call('chat', 'chat', 'vote', {conversation_address: conversation_addr, message_address: poll_addr, option: 1})
+    await s.consistency()
+
+    const results = await player1.call('chat', 'chat', 'get_poll_results', {message_address: poll_addr})
+    t.deepEqual(results.Ok.options.map(o => o.votes), [1, 1], 'a changed vote replaces the earlier one')
+    t.deepEqual(results.Ok.total_votes, 2)
+
+    await new Promise(resolve => setTimeout(resolve, Math.max(0, poll.closes_at - Date.now()) + 1000))
+    const late_vote = await player2.call('chat', 'chat', 'vote', {conversation_address: conversation_addr, message_address: poll_addr, option: 0})
+    t.notEqual(late_vote.Err, undefined, 'cannot vote after the poll closes')
+  })
+
+  scenario('Drafts are kept per conversation and cleared when a message is posted', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const create_result = await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)
+    await s.consistency()
+    const conversation_addr = create_result.Ok
+
+    await player1.call('chat', 'chat', 'save_draft', {conversation_address: conversation_addr, message: {...testMessage, payload: 'half written'}})
+    const draft = await player1.call('chat', 'chat', 'get_draft', {conversation_address: conversation_addr})
+    t.deepEqual(draft.Ok.message.payload, 'half written')
+
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: testMessage})
+    const cleared = await player1.call('chat', 'chat', 'get_draft', {conversation_address: conversation_addr})
+    t.deepEqual(cleared.Ok.message, null, 'posting clears the draft')
+  })
+
+  scenario('Messages can be forwarded and quoted with their original attribution', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const source_addr = (await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)).Ok
+    const target_addr = (await player2.call('chat', 'chat', 'start_conversation', {name: 'elsewhere', description: ''})).Ok
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: source_addr, message: {...testMessage, timestamp: 5}})
+    await s.consistency()
+    const original = (await player2.call('chat', 'chat', 'get_messages', {address: source_addr})).Ok[0]
+
+    const forwarded = await player2.call('chat', 'chat', 'forward_message', {message_address: original.address, source_conversation: source_addr, target_conversation: target_addr, timestamp: 10})
+    t.deepEqual(forwarded.Err, undefined)
+    const wrong_source = await player2.call('chat', 'chat', 'forward_message', {message_address: original.address, source_conversation: target_addr, target_conversation: target_addr, timestamp: 10})
+    t.notEqual(wrong_source.Err, undefined, 'the message must have been posted in the source conversation')
+
+    const quote = {conversation_address: source_addr, message_address: original.address}
+    await player2.call('chat', 'chat', 'post_message', {conversation_address: source_addr, message: {...testMessage, timestamp: 11, payload: 'agreed', quote}})
+    await s.consistency()
+
+    const target_messages = (await player2.call('chat', 'chat', 'get_messages', {address: target_addr})).Ok
+    t.deepEqual(target_messages[0].entry.payload, testMessage.payload)
+    t.deepEqual(target_messages[0].entry.reference.kind, 'forward')
+    t.deepEqual(target_messages[0].entry.reference.author, player1.instance('chat').agentAddress, 'credited to the original author')
+    t.deepEqual(target_messages[0].entry.reference.timestamp, 5)
+
+    const reply = (await player1.call('chat', 'chat', 'get_messages', {address: source_addr})).Ok.find(m => m.entry.payload === 'agreed')
+    t.deepEqual(reply.entry.reference.kind, 'quote')
+    t.deepEqual(reply.entry.reference.message_address, original.address)
+  })
+
+  scenario('Messages accept structured and legacy meta but reject invalid meta', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const conversation_addr = (await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)).Ok
+    await s.consistency()
+
+    const structured = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, payload: 'structured', meta: {version: 1, fields: {client: 'tests'}}}})
+    t.deepEqual(structured.Err, undefined)
+    const legacy = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, payload: 'legacy', meta: 'plain string meta'}})
+    t.deepEqual(legacy.Err, undefined, 'plain string meta is still accepted')
+
+    const future_version = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, meta: {version: 2, fields: {}}}})
+    t.notEqual(future_version.Err, undefined, 'unsupported meta versions are rejected')
+    const bad_key = await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, meta: {version: 1, fields: {'Bad Key': 1}}}})
+    t.notEqual(bad_key.Err, undefined, 'meta keys must be lowercase identifiers')
+    await s.consistency()
+
+    const messages = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok
+    t.deepEqual(messages.length, 2)
+    t.deepEqual(messages.find(m => m.entry.payload === 'structured').entry.meta.fields.client, 'tests')
+    t.deepEqual(messages.find(m => m.entry.payload === 'legacy').entry.meta, 'plain string meta')
+  })
+
+  scenario('Scheduled messages are posted once due with the time they were scheduled for', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const conversation_addr = (await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)).Ok
+    await s.consistency()
+
+    const spec = {...testMessage, payload: 'see you tomorrow'}
+    const first = await player1.call('chat', 'chat', 'schedule_message', {conversation_address: conversation_addr, spec, send_at: 100})
+    const second = await player1.call('chat', 'chat', 'schedule_message', {conversation_address: conversation_addr, spec, send_at: 100})
+    t.notEqual(first.Ok, second.Ok, 'scheduling the same message twice gives two scheduled messages')
+    const invalid = await player1.call('chat', 'chat', 'schedule_message', {conversation_address: conversation_addr, spec: {...testMessage, payload: ''}, send_at: 100})
+
+    const early = await player1.call('chat', 'chat', 'send_scheduled_messages', {now: 50})
+    t.deepEqual(early.Ok, [], 'nothing is due yet')
+
+    const sent = await player1.call('chat', 'chat', 'send_scheduled_messages', {now: 200})
+    t.deepEqual(sent.Ok.sort(), [first.Ok, second.Ok].sort())
+    await s.consistency()
+
+    const messages = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok
+    t.deepEqual(messages.length, 2)
+    t.ok(messages.every(m => m.entry.payload === spec.payload && m.entry.timestamp === 100), 'posted with the scheduled time')
+
+    const pending = (await player1.call('chat', 'chat', 'list_scheduled_messages', {})).Ok
+    t.deepEqual(pending.map(p => p.address).includes(invalid.Ok), false, 'a message that fails validation is not retried')
+  })
+
+  scenario('Expired messages are hidden and can be cleaned up by their author', async (s, t) => {
+    const {player1} = await s.players({player1: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    const conversation_addr = (await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)).Ok
+    await s.consistency()
+
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, timestamp: 10, payload: 'forever'}})
+    await player1.call('chat', 'chat', 'set_conversation_ttl', {conversation_address: conversation_addr, default_ttl: 100})
+    await s.consistency()
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, timestamp: 20, payload: 'short lived'}})
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, timestamp: 30, payload: 'own expiry', expires_at: 1000}})
+    await s.consistency()
+
+    const messages = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok
+    t.deepEqual(messages.find(m => m.entry.payload === 'short lived').entry.expires_at, 120, 'the default ttl is applied')
+    t.deepEqual(messages.find(m => m.entry.payload === 'own expiry').entry.expires_at, 1000, 'an explicit expiry wins')
+
+    const visible = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr, now: 500})).Ok
+    t.deepEqual(visible.map(m => m.entry.payload).sort(), ['forever', 'own expiry'])
+
+    const removed = await player1.call('chat', 'chat', 'cleanup_expired_messages', {conversation_address: conversation_addr, now: 500})
+    t.deepEqual(removed.Ok.length, 1)
+    await s.consistency()
+    const remaining = (await player1.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok
+    t.deepEqual(remaining.map(m => m.entry.payload).sort(), ['forever', 'own expiry'], 'only the expired message is removed')
+  })
+
+  scenario('Notification levels decide which signals reach the UI', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const player2_addr = player2.instance('chat').agentAddress
+    const signals = []
+    player2.onSignal = signal => signals.push(signal)
+
+    const levels = ['all', 'mentions_only', 'none']
+    const conversations = {}
+    for (const level of levels) {
+      conversations[level] = (await player1.call('chat', 'chat', 'start_conversation', {name: level, description: ''})).Ok
+    }
+    await s.consistency()
+    for (const level of levels) {
+      await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversations[level]})
+      await player2.call('chat', 'chat', 'set_notification_level', {conversation_address: conversations[level], level})
+    }
+    await s.consistency()
+
+    signals.length = 0
+    for (const level of levels) {
+      await player1.call('chat', 'chat', 'post_message', {conversation_address: conversations[level], message: {...testMessage, payload: 'hey @player2', mentions: [player2_addr]}})
+    }
+    await s.consistency()
+
+    const received = level => signals
+      .map(signal => ({name: signal.signal.name, args: JSON.parse(signal.signal.arguments)}))
+      .filter(signal => signal.args.conversationAddress === conversations[level])
+      .map(signal => signal.name)
+      .sort()
+    t.deepEqual(received('all'), ['new_convo_message', 'new_mention'], 'all signals every message')
+    t.deepEqual(received('mentions_only'), ['new_mention'], 'mentions only signals just the mention')
+    t.deepEqual(received('none'), [], 'none signals nothing')
+  })
+
+  scenario('Blocked agents are filtered from messages and mentions until unblocked', async (s, t) => {
+    const {player1, player2} = await s.players({player1: config1, player2: config1}, true)
+    await player1.call('chat', 'chat', 'register', {name: 'player1', avatar_url: ''})
+    await player2.call('chat', 'chat', 'register', {name: 'player2', avatar_url: ''})
+    const player1_addr = player1.instance('chat').agentAddress
+    const player2_addr = player2.instance('chat').agentAddress
+    const conversation_addr = (await player1.call('chat', 'chat', 'start_conversation', testNewChannelParams)).Ok
+    await s.consistency()
+    await player2.call('chat', 'chat', 'join_conversation', {conversation_address: conversation_addr})
+
+    await player1.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, payload: 'hey @player2', mentions: [player2_addr]}})
+    await player2.call('chat', 'chat', 'post_message', {conversation_address: conversation_addr, message: {...testMessage, payload: 'hello'}})
+    await s.consistency()
+
+    await player2.call('chat', 'chat', 'block_agent', {agent_address: player1_addr})
+    await player2.call('chat', 'chat', 'block_agent', {agent_address: player1_addr})
+    t.deepEqual((await player2.call('chat', 'chat', 'get_blocked_agents', {})).Ok, [player1_addr], 'blocking twice lists the agent once')
+
+    const filtered = (await player2.call('chat', 'chat', 'get_messages', {address: conversation_addr, exclude_blocked: true})).Ok
+    t.deepEqual(filtered.map(m => m.entry.payload), ['hello'])
+    const unfiltered = (await player2.call('chat', 'chat', 'get_messages', {address: conversation_addr})).Ok
+    t.deepEqual(unfiltered.length, 2, 'blocked messages are still there when not excluded')
+    t.deepEqual((await player2.call('chat', 'chat', 'get_my_mentions', {})).Ok, [], 'mentions by blocked agents are hidden')
+
+    await player2.call('chat', 'chat', 'unblock_agent', {agent_address: player1_addr})
+    t.deepEqual((await player2.call('chat', 'chat', 'get_blocked_agents', {})).Ok, [])
+    const restored = (await player2.call('chat', 'chat', 'get_messages', {address: conversation_addr, exclude_blocked: true})).Ok
+    t.deepEqual(restored.length, 2)
+    t.deepEqual((await player2.call('chat', 'chat', 'get_my_mentions', {})).Ok.length, 1)
+  })
 }
@@ -28,6 +28,7 @@ const orchestrator = new Orchestrator({
 })
 
 require('./agent/messages')(orchestrator.registerScenario)
+require('./agent/conversations')(orchestrator.registerScenario)
 // require('./scenario/4-agents')(orchestrator.registerScenario)
 
 orchestrator.run()
@@ -0,0 +1,56 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_persistence_api::cas::content::Address,
+    AGENT_ADDRESS,
+};
+use std::collections::HashSet;
+use crate::activity::{ActivitySummary, ConversationActivity};
+use crate::utils::{get_links_and_load_type, query_local_entries, query_local_links};
+use crate::{ACTIVITY_ENTRY, ACTIVITY_LINK_TYPE};
+
+/// Recount this agent's counter for the conversation after they linked or removed a message.
+/// Call it once per link so every update counts exactly one message.
+pub(crate) fn record_activity(conversation_address: &Address) -> ZomeApiResult<()> {
+    let agent: Address = AGENT_ADDRESS.to_string().into();
+    let activity = ConversationActivity::count(conversation_address, &agent, &query_local_links()?);
+    let previous = query_local_entries::<ConversationActivity>(ACTIVITY_ENTRY)?
+        .into_iter()
+        .filter(|result| &result.entry.conversation_address == conversation_address)
+        .last();
+    match previous {
+        Some(previous) => {
+            hdk::update_entry(Entry::App(ACTIVITY_ENTRY.into(), activity.into()), &previous.address)?;
+        }
+        None => {
+            let activity_address =
+                hdk::commit_entry(&Entry::App(ACTIVITY_ENTRY.into(), activity.into()))?;
+            hdk::link_entries(conversation_address, &activity_address, ACTIVITY_LINK_TYPE, "")?;
+        }
+    }
+    Ok(())
+}
+
+/// Add up the counters of every agent that has posted to or removed messages from the
+/// conversation. Expired messages only stop counting once they have been cleaned up.
+pub fn get_activity_summary(conversation_address: &Address) -> ZomeApiResult<ActivitySummary> {
+    let mut counted = HashSet::new();
+    let (posted, removed, last_activity) = get_links_and_load_type::<ConversationActivity>(
+        conversation_address,
+        LinkMatch::Exactly(ACTIVITY_LINK_TYPE),
+        LinkMatch::Any,
+    )?
+    .into_iter()
+    .filter(|result| counted.insert(result.entry.agent.clone()))
+    .fold((0, 0, None), |(posted, removed, last_activity), result| {
+        (
+            posted + result.entry.message_count,
+            removed + result.entry.removed_count,
+            last_activity.max(result.entry.last_posted_at),
+        )
+    });
+    Ok(ActivitySummary {
+        message_count: posted.saturating_sub(removed),
+        last_activity,
+    })
+}
@@ -0,0 +1,151 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::{
+        chain_header::ChainHeader, dna::entry_types::Sharing, entry::Entry, link::Link,
+        validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+
+use crate::utils::{author_chain, link_and_sources, parse_iso8601_millis};
+
+pub mod handlers;
+
+/// How much one agent has posted to a conversation. Each agent keeps their own counter
+/// up to date as they post and remove messages, so activity can be summed without loading
+/// any messages. Counters are recounted from the `message_in` links on the agent's chain
+/// every time, and validation recounts them the same way.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, DefaultJson)]
+pub struct ConversationActivity {
+    pub conversation_address: Address,
+    pub agent: Address,
+    pub message_count: u64,
+    // messages the agent took out of the conversation, their own or as a moderator
+    #[serde(default)]
+    pub removed_count: u64,
+    #[serde(default)]
+    pub last_posted_at: Option<u64>,
+}
+
+impl ConversationActivity {
+    /// Count the messages an agent linked to and removed from a conversation on their chain
+    pub fn count(
+        conversation_address: &Address,
+        agent: &Address,
+        chain: &[(ChainHeader, Entry)],
+    ) -> ConversationActivity {
+        let mut activity = ConversationActivity {
+            conversation_address: conversation_address.clone(),
+            agent: agent.clone(),
+            message_count: 0,
+            removed_count: 0,
+            last_posted_at: None,
+        };
+        for (header, entry) in chain {
+            match entry {
+                Entry::LinkAdd(link_data) if is_message_link(&link_data.link, conversation_address) => {
+                    activity.message_count += 1;
+                    activity.last_posted_at = activity
+                        .last_posted_at
+                        .max(parse_iso8601_millis(&header.timestamp().to_string()));
+                }
+                Entry::LinkRemove((link_data, _)) if is_message_link(&link_data.link, conversation_address) => {
+                    activity.removed_count += 1;
+                }
+                _ => {}
+            }
+        }
+        activity
+    }
+
+    fn total(&self) -> u64 {
+        self.message_count + self.removed_count
+    }
+}
+
+fn is_message_link(link: &Link, conversation_address: &Address) -> bool {
+    link.base() == conversation_address && link.link_type() == MESSAGE_LINK_TYPE_TO
+}
+
+/// Checks that a counter matches the agent's chain and counts one more message than before
+fn validate_count(
+    activity: &ConversationActivity,
+    previous_total: u64,
+    chain: &[(ChainHeader, Entry)],
+) -> Result<(), String> {
+    if activity.total() != previous_total + 1 {
+        return Err("Activity must be counted one message at a time".into());
+    }
+    if activity != &ConversationActivity::count(&activity.conversation_address, &activity.agent, chain) {
+        return Err("Activity must match the messages linked on the agent's chain".into());
+    }
+    Ok(())
+}
+
+/// The activity of every agent in a conversation added together
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ActivitySummary {
+    pub message_count: u64,
+    pub last_activity: Option<u64>,
+}
+
+use crate::{ACTIVITY_ENTRY, ACTIVITY_LINK_TYPE, MESSAGE_LINK_TYPE_TO};
+
+pub fn activity_definition() -> ValidatingEntryType {
+    entry!(
+        name: ACTIVITY_ENTRY,
+        description: "An agent's running count of their posts to a conversation",
+        sharing: Sharing::Public,
+
+        // the agent's chain is needed to recount their messages
+        validation_package: || {
+            hdk::ValidationPackageDefinition::ChainFull
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<ConversationActivity>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    if !validation_data.sources().contains(&entry.agent) {
+                        return Err("Agents can only count their own activity".into());
+                    }
+                    validate_count(&entry, 0, &author_chain(&validation_data))
+                },
+                EntryValidationData::Modify{new_entry, old_entry, validation_data, ..} => {
+                    if !validation_data.sources().contains(&old_entry.agent) {
+                        return Err("Agents can only count their own activity".into());
+                    }
+                    if new_entry.conversation_address != old_entry.conversation_address || new_entry.agent != old_entry.agent {
+                        return Err("Activity can only be counted for the same agent and conversation".into());
+                    }
+                    validate_count(&new_entry, old_entry.total(), &author_chain(&validation_data))
+                },
+                _ => Err("Cannot delete activity".into()),
+            }
+        },
+
+        links: [
+            from!(
+                "public_conversation",
+                link_type: ACTIVITY_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources) = link_and_sources(validation_data);
+                    let activity: ConversationActivity = get_as_type(link.target().clone())
+                        .map_err(|_| String::from("Activity could not be found"))?;
+                    if &activity.conversation_address == link.base() && sources.contains(&activity.agent) {
+                        Ok(())
+                    } else {
+                        Err("Activity can only be linked from its conversation by the agent".into())
+                    }
+                }
+            )
+        ]
+    )
+}
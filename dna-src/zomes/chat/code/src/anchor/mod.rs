@@ -4,6 +4,10 @@ use hdk::{
     holochain_json_api::json::RawString,
 };
 
+use crate::hashtag::{validate_hashtag_link, validate_tagged_message_link, validate_tagged_message_unlink};
+use crate::utils::header_time;
+use crate::{CATEGORY_LINK_TYPE, COMMUNITY_LINK_TYPE, HASHTAG_LINK_TYPE, TAGGED_MESSAGE_LINK_TYPE};
+
 pub fn anchor_definition() -> ValidatingEntryType {
     entry!(
         name: "anchor",
@@ -39,6 +43,80 @@ pub fn anchor_definition() -> ValidatingEntryType {
                     hdk::ValidationPackageDefinition::Entry
                 },
 
+                validation: |_validation_data: hdk::LinkValidationData| {
+                    Ok(())
+                }
+            ),
+            to!(
+                "anchor",
+                link_type: HASHTAG_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, .. } => {
+                            validate_hashtag_link(link.link.base(), link.link.target(), link.link.tag())
+                        },
+                        hdk::LinkValidationData::LinkRemove { .. } => {
+                            Err("Hashtag links cannot be removed".into())
+                        },
+                    }
+                }
+            ),
+            to!(
+                "message",
+                link_type: TAGGED_MESSAGE_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            validate_tagged_message_link(
+                                link.link.base(),
+                                link.link.target(),
+                                link.link.tag(),
+                                &validation_data.sources(),
+                                header_time(&validation_data)?,
+                            )
+                        },
+                        // removed along with the message by its author or a moderator
+                        hdk::LinkValidationData::LinkRemove { link, validation_data } => {
+                            validate_tagged_message_unlink(
+                                link.link.target(),
+                                link.link.tag(),
+                                &validation_data.sources(),
+                                header_time(&validation_data)?,
+                            )
+                        },
+                    }
+                }
+            ),
+            to!(
+                "category",
+                link_type: CATEGORY_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |_validation_data: hdk::LinkValidationData| {
+                    Ok(())
+                }
+            ),
+            to!(
+                "community",
+                link_type: COMMUNITY_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
                 validation: |_validation_data: hdk::LinkValidationData| {
                     Ok(())
                 }
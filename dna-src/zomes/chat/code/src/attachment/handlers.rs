@@ -0,0 +1,38 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::entry::Entry,
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+use crate::attachment::{AttachmentChunk, AttachmentManifest};
+use crate::{ATTACHMENT_CHUNK_ENTRY, ATTACHMENT_ENTRY};
+
+/// Store one chunk of an attachment. Clients upload every chunk this way before
+/// committing the manifest that ties them together.
+pub fn handle_upload_attachment_chunk(data: Vec<u8>) -> ZomeApiResult<Address> {
+    let entry = Entry::App(ATTACHMENT_CHUNK_ENTRY.into(), AttachmentChunk { data }.into());
+    hdk::commit_entry(&entry)
+}
+
+pub fn handle_upload_attachment(
+    name: String,
+    mime_type: String,
+    size: u64,
+    chunks: Vec<Address>,
+) -> ZomeApiResult<Address> {
+    let manifest = AttachmentManifest {
+        name,
+        mime_type,
+        size,
+        chunks,
+    };
+    hdk::commit_entry(&Entry::App(ATTACHMENT_ENTRY.into(), manifest.into()))
+}
+
+pub fn handle_get_attachment(address: Address) -> ZomeApiResult<AttachmentManifest> {
+    get_as_type(address)
+}
+
+pub fn handle_get_attachment_chunk(address: Address) -> ZomeApiResult<AttachmentChunk> {
+    get_as_type(address)
+}
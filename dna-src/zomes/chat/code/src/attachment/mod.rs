@@ -0,0 +1,105 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::{dna::entry_types::Sharing, validation::EntryValidationData},
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+
+use validator::Validate;
+
+pub mod handlers;
+
+pub const CHUNK_SIZE: usize = 64 * 1024;
+pub const MAX_ATTACHMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// A piece of an attachment's content. Chunks are addressed by their content so
+/// identical chunks are only ever stored once.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct AttachmentChunk {
+    pub data: Vec<u8>,
+}
+
+/// Describes an attachment and lists the chunks that make up its content, in order
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson, Validate)]
+pub struct AttachmentManifest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 3, max = 255))]
+    pub mime_type: String,
+    pub size: u64,
+    pub chunks: Vec<Address>,
+}
+
+fn validate_manifest(manifest: &AttachmentManifest) -> Result<(), String> {
+    manifest.validate().map_err(|e| e.to_string())?;
+    let mut parts = manifest.mime_type.split('/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(kind), Some(subtype), None) if !kind.is_empty() && !subtype.is_empty() => (),
+        _ => return Err(format!("Invalid MIME type {}", manifest.mime_type)),
+    }
+    if manifest.size == 0 || manifest.size > MAX_ATTACHMENT_SIZE {
+        return Err(format!("Attachments must be between 1 and {} bytes", MAX_ATTACHMENT_SIZE));
+    }
+    let expected_chunks = (manifest.size + CHUNK_SIZE as u64 - 1) / CHUNK_SIZE as u64;
+    if manifest.chunks.len() as u64 != expected_chunks {
+        return Err("Number of chunks does not match the attachment size".into());
+    }
+    let mut total_size = 0;
+    for chunk_address in &manifest.chunks {
+        let chunk: AttachmentChunk = get_as_type(chunk_address.clone())
+            .map_err(|_| format!("Attachment chunk {} could not be found", chunk_address))?;
+        total_size += chunk.data.len() as u64;
+    }
+    if total_size != manifest.size {
+        return Err("Attachment chunks do not add up to the attachment size".into());
+    }
+    Ok(())
+}
+
+use crate::{ATTACHMENT_CHUNK_ENTRY, ATTACHMENT_ENTRY};
+
+pub fn attachment_chunk_definition() -> ValidatingEntryType {
+    entry!(
+        name: ATTACHMENT_CHUNK_ENTRY,
+        description: "A chunk of the content of an attachment",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<AttachmentChunk>| {
+            match validation_data {
+                EntryValidationData::Create{entry, ..} => {
+                    if entry.data.is_empty() || entry.data.len() > CHUNK_SIZE {
+                        Err(format!("Attachment chunks must be between 1 and {} bytes", CHUNK_SIZE))
+                    } else {
+                        Ok(())
+                    }
+                },
+                _ => Err("Cannot modify or delete an attachment chunk".into()),
+            }
+        }
+    )
+}
+
+pub fn attachment_definition() -> ValidatingEntryType {
+    entry!(
+        name: ATTACHMENT_ENTRY,
+        description: "The manifest of a file or image attached to a message",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<AttachmentManifest>| {
+            match validation_data {
+                EntryValidationData::Create{entry, ..} => validate_manifest(&entry),
+                _ => Err("Cannot modify or delete an attachment".into()),
+            }
+        }
+    )
+}
@@ -0,0 +1,68 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_persistence_api::cas::content::Address,
+    AGENT_ADDRESS,
+};
+use std::collections::HashSet;
+use crate::audit::{AuditEntry, ModerationAction, AUDIT_PAGE_SIZE};
+use crate::utils::{get_links_and_load_type, query_local_entries, GetLinksLoadResult};
+use crate::{AUDIT_ENTRY, AUDIT_LINK_TYPE};
+
+/// Every audit entry of a conversation, newest first. Different moderators acting at the
+/// same time can end up with the same sequence number, the address breaks those ties.
+fn get_audit_entries(conversation_address: &Address) -> ZomeApiResult<Vec<GetLinksLoadResult<AuditEntry>>> {
+    let mut result = get_links_and_load_type::<AuditEntry>(
+        conversation_address,
+        LinkMatch::Exactly(AUDIT_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    result.retain(|e| uniques.insert(e.address.clone()));
+    result.sort_by(|a, b| {
+        (b.entry.sequence, b.address.to_string()).cmp(&(a.entry.sequence, a.address.to_string()))
+    });
+    Ok(result)
+}
+
+/// Append a moderation action this agent took to the conversation's audit log. The next
+/// sequence number is read from the link tags, and is always past this agent's own last one.
+pub(crate) fn record_moderation(conversation_address: &Address, action: ModerationAction) -> ZomeApiResult<Address> {
+    let latest_linked = hdk::get_links(conversation_address, LinkMatch::Exactly(AUDIT_LINK_TYPE), LinkMatch::Any)?
+        .links()
+        .into_iter()
+        .filter_map(|link| link.tag.parse::<u64>().ok())
+        .max();
+    let latest_own = query_local_entries::<AuditEntry>(AUDIT_ENTRY)?
+        .into_iter()
+        .filter(|result| &result.entry.conversation_address == conversation_address)
+        .map(|result| result.entry.sequence)
+        .max();
+    let sequence = latest_linked.max(latest_own).map(|latest| latest + 1).unwrap_or(0);
+    let audit_entry = AuditEntry {
+        conversation_address: conversation_address.clone(),
+        sequence,
+        actor: AGENT_ADDRESS.to_string().into(),
+        action,
+    };
+    let audit_address = hdk::commit_entry(&Entry::App(AUDIT_ENTRY.into(), audit_entry.into()))?;
+    hdk::link_entries(
+        conversation_address,
+        &audit_address,
+        AUDIT_LINK_TYPE,
+        sequence.to_string().as_str(),
+    )?;
+    Ok(audit_address)
+}
+
+/// One page of a conversation's audit log, page 0 holding the most recent actions
+pub fn handle_get_audit_log(
+    conversation_address: Address,
+    page: usize,
+) -> ZomeApiResult<Vec<GetLinksLoadResult<AuditEntry>>> {
+    Ok(get_audit_entries(&conversation_address)?
+        .into_iter()
+        .skip(page * AUDIT_PAGE_SIZE)
+        .take(AUDIT_PAGE_SIZE)
+        .collect())
+}
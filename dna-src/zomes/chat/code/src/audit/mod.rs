@@ -0,0 +1,138 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::{
+        chain_header::ChainHeader, dna::entry_types::Sharing, entry::Entry,
+        validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+use std::convert::TryFrom;
+
+use crate::conversation::{validate_moderator, JoinPolicy, PostingPolicy};
+use crate::utils::{author_chain, header_time};
+
+pub mod handlers;
+
+pub const AUDIT_PAGE_SIZE: usize = 20;
+
+/// Something a moderator did to a conversation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    Kick(Address),
+    Ban(Address),
+    DeleteMessage(Address),
+    Pin(Address),
+    Unpin(Address),
+    ChangeTopic(String),
+    SetTtl(Option<u64>),
+    SetPostingPolicy(PostingPolicy),
+    SetJoinPolicy(JoinPolicy),
+    ApproveJoin(Address),
+    DenyJoin(Address),
+    MoveToCategory(Option<Address>),
+    SetTags(Vec<String>),
+}
+
+/// An append-only record of a moderation action. The sequence number orders
+/// the log, it is one more than the highest the actor could see when acting.
+/// Each actor's sequence numbers only go up, so one actor never reuses a number.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, DefaultJson)]
+pub struct AuditEntry {
+    pub conversation_address: Address,
+    pub sequence: u64,
+    pub actor: Address,
+    pub action: ModerationAction,
+}
+
+/// Checks that the actor has not recorded the same or a later sequence number
+/// for the conversation before, going by their own chain
+fn validate_sequence(audit_entry: &AuditEntry, author_chain: &[(ChainHeader, Entry)]) -> Result<(), String> {
+    let reused = author_chain
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            Entry::App(entry_type, entry_value) if String::from(entry_type.clone()) == AUDIT_ENTRY => {
+                AuditEntry::try_from(entry_value.clone()).ok()
+            }
+            _ => None,
+        })
+        .any(|earlier| {
+            &earlier != audit_entry
+                && earlier.conversation_address == audit_entry.conversation_address
+                && earlier.sequence >= audit_entry.sequence
+        });
+    if reused {
+        Err("Audit sequence numbers must increase for each actor".into())
+    } else {
+        Ok(())
+    }
+}
+
+use crate::{AUDIT_ENTRY, AUDIT_LINK_TYPE};
+
+pub fn audit_entry_definition() -> ValidatingEntryType {
+    entry!(
+        name: AUDIT_ENTRY,
+        description: "A record of a moderation action taken in a conversation",
+        sharing: Sharing::Public,
+
+        // the actor's chain is needed to check they don't reuse a sequence number
+        validation_package: || {
+            hdk::ValidationPackageDefinition::ChainFull
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<AuditEntry>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    if !validation_data.sources().contains(&entry.actor) {
+                        return Err("Moderation actions can only be recorded by the moderator who took them".into());
+                    }
+                    validate_sequence(&entry, &author_chain(&validation_data))?;
+                    validate_moderator(
+                        &entry.conversation_address,
+                        &[entry.actor.clone()],
+                        header_time(&validation_data)?,
+                    )
+                },
+                _ => Err("The audit log is append-only".into()),
+            }
+        },
+
+        links: [
+            from!(
+                "public_conversation",
+                link_type: AUDIT_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            let audit_entry: AuditEntry = get_as_type(link.link.target().clone())
+                                .map_err(|_| String::from("Audit entry could not be found"))?;
+                            if &audit_entry.conversation_address != link.link.base() {
+                                return Err("Audit entries can only be linked from their conversation".into());
+                            }
+                            if link.link.tag() != &audit_entry.sequence.to_string() {
+                                return Err("Audit links must be tagged with the entry's sequence number".into());
+                            }
+                            if validation_data.sources().contains(&audit_entry.actor) {
+                                Ok(())
+                            } else {
+                                Err("Only the actor can add an entry to the audit log".into())
+                            }
+                        },
+                        hdk::LinkValidationData::LinkRemove { .. } => {
+                            Err("The audit log is append-only".into())
+                        },
+                    }
+                }
+            )
+        ]
+    )
+}
@@ -0,0 +1,46 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::entry::Entry,
+    holochain_persistence_api::cas::content::Address,
+};
+use crate::block::BlockList;
+use crate::utils::query_local_entries;
+use crate::BLOCK_LIST_ENTRY;
+
+fn latest_block_list() -> ZomeApiResult<BlockList> {
+    Ok(query_local_entries::<BlockList>(BLOCK_LIST_ENTRY)?
+        .into_iter()
+        .map(|result| result.entry)
+        .last()
+        .unwrap_or_default())
+}
+
+fn commit_block_list(block_list: BlockList) -> ZomeApiResult<Address> {
+    hdk::commit_entry(&Entry::App(BLOCK_LIST_ENTRY.into(), block_list.into()))
+}
+
+pub fn handle_get_blocked_agents() -> ZomeApiResult<Vec<Address>> {
+    Ok(latest_block_list()?.blocked)
+}
+
+pub fn is_blocked(agent_address: &Address) -> ZomeApiResult<bool> {
+    Ok(latest_block_list()?.blocked.contains(agent_address))
+}
+
+pub fn handle_block_agent(agent_address: Address) -> ZomeApiResult<()> {
+    let mut block_list = latest_block_list()?;
+    if !block_list.blocked.contains(&agent_address) {
+        block_list.blocked.push(agent_address);
+        commit_block_list(block_list)?;
+    }
+    Ok(())
+}
+
+pub fn handle_unblock_agent(agent_address: Address) -> ZomeApiResult<()> {
+    let mut block_list = latest_block_list()?;
+    if block_list.blocked.contains(&agent_address) {
+        block_list.blocked.retain(|blocked| blocked != &agent_address);
+        commit_block_list(block_list)?;
+    }
+    Ok(())
+}
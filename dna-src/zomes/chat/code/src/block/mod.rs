@@ -0,0 +1,33 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::dna::entry_types::Sharing,
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+pub mod handlers;
+
+/// The agents this agent does not want to hear from. Kept privately on their own
+/// chain, the latest list replaces earlier ones.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson, Default)]
+pub struct BlockList {
+    pub blocked: Vec<Address>,
+}
+
+use crate::BLOCK_LIST_ENTRY;
+
+pub fn block_list_definition() -> ValidatingEntryType {
+    entry!(
+        name: BLOCK_LIST_ENTRY,
+        description: "The agents an agent has blocked",
+        sharing: Sharing::Private,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |_validation_data: hdk::EntryValidationData<BlockList>| {
+            Ok(())
+        }
+    )
+}
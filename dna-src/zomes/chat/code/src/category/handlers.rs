@@ -0,0 +1,149 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+    utils::get_as_type,
+    AGENT_ADDRESS,
+};
+use std::{collections::HashSet, convert::TryFrom};
+use crate::audit::{handlers::record_moderation, ModerationAction};
+use crate::category::{Category, CategoryNode, ConversationTree};
+use crate::community::{commit_scope, is_admin, scope_address};
+use crate::conversation::handlers::{handle_get_all_public_conversations, require_moderator};
+use crate::conversation::Conversation;
+use crate::utils::{get_links_and_load_type, GetLinksLoadResult};
+use crate::{CATEGORY_ENTRY, CATEGORY_LINK_TYPE, IN_CATEGORY_LINK_TYPE};
+
+/// Create a category for grouping conversations, which this agent then moderates. Categories
+/// of a community are created by its admins. Any agent may create a global category, but only
+/// moderators of a conversation can move it into one.
+pub fn handle_create_category(
+    name: String,
+    position: u64,
+    community: Option<Address>,
+) -> ZomeApiResult<Address> {
+    if let Some(community_address) = &community {
+        if !is_admin(community_address, &AGENT_ADDRESS)? {
+            return Err(ZomeApiError::Internal("Only admins of the community can do this".into()));
+        }
+    }
+    let category = Category {
+        name,
+        position,
+        community: community.clone(),
+        moderators: vec![AGENT_ADDRESS.to_string().into()],
+    };
+    let category_address = hdk::commit_entry(&Entry::App(CATEGORY_ENTRY.into(), category.into()))?;
+    let list_address = commit_scope(&community, "categories")?;
+    hdk::link_entries(&list_address, &category_address, CATEGORY_LINK_TYPE, "")?;
+    Ok(category_address)
+}
+
+/// Rename or reorder a category this agent moderates, or one of a community this agent administers
+pub fn handle_update_category(
+    category_address: Address,
+    name: String,
+    position: u64,
+) -> ZomeApiResult<()> {
+    let latest_entry = hdk::get_entry(&category_address)?
+        .ok_or_else(|| ZomeApiError::Internal("Category could not be found".into()))?;
+    let mut category = match &latest_entry {
+        Entry::App(_, entry_value) => Category::try_from(entry_value.clone())
+            .map_err(|_| ZomeApiError::Internal("Invalid category data".into()))?,
+        _ => return Err(ZomeApiError::Internal("Category could not be found".into())),
+    };
+    let is_community_admin = match &category.community {
+        Some(community_address) => is_admin(community_address, &AGENT_ADDRESS)?,
+        None => false,
+    };
+    if !category.is_moderated_by(&AGENT_ADDRESS) && !is_community_admin {
+        return Err(ZomeApiError::Internal("Only moderators of the category can do this".into()));
+    }
+    category.name = name;
+    category.position = position;
+    hdk::update_entry(
+        Entry::App(CATEGORY_ENTRY.into(), category.into()),
+        &latest_entry.address(),
+    )?;
+    Ok(())
+}
+
+/// All categories of a community ordered by position, then by name
+fn get_categories(community: &Option<Address>) -> ZomeApiResult<Vec<GetLinksLoadResult<Category>>> {
+    let list_address = scope_address(community, "categories")?;
+    let mut categories = get_links_and_load_type::<Category>(
+        &list_address,
+        LinkMatch::Exactly(CATEGORY_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    categories.retain(|e| uniques.insert(e.address.clone()));
+    categories.sort_by(|a, b| {
+        (a.entry.position, &a.entry.name).cmp(&(b.entry.position, &b.entry.name))
+    });
+    Ok(categories)
+}
+
+fn get_category_conversations(category_address: &Address) -> ZomeApiResult<Vec<GetLinksLoadResult<Conversation>>> {
+    let mut conversations = get_links_and_load_type::<Conversation>(
+        category_address,
+        LinkMatch::Exactly(IN_CATEGORY_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    conversations.retain(|e| uniques.insert(e.address.clone()));
+    conversations.sort_by(|a, b| a.entry.name.cmp(&b.entry.name));
+    Ok(conversations)
+}
+
+/// Put a conversation in a category of its community, taking it out of any it was in before.
+/// Moving it to no category leaves it uncategorized.
+pub fn handle_move_conversation_to_category(
+    conversation_address: Address,
+    category_address: Option<Address>,
+) -> ZomeApiResult<()> {
+    require_moderator(&conversation_address)?;
+    let conversation: Conversation = get_as_type(conversation_address.clone())?;
+    if let Some(category_address) = &category_address {
+        let category: Category = get_as_type(category_address.clone())?;
+        if category.community != conversation.community {
+            return Err(ZomeApiError::Internal(
+                "Category belongs to a different community".into(),
+            ));
+        }
+    }
+    for category in get_categories(&conversation.community)? {
+        for link in hdk::get_links(&category.address, LinkMatch::Exactly(IN_CATEGORY_LINK_TYPE), LinkMatch::Any)?.links() {
+            if link.address == conversation_address {
+                hdk::remove_link(&category.address, &conversation_address, IN_CATEGORY_LINK_TYPE, link.tag.as_str())?;
+            }
+        }
+    }
+    if let Some(category_address) = &category_address {
+        hdk::link_entries(category_address, &conversation_address, IN_CATEGORY_LINK_TYPE, "")?;
+    }
+    record_moderation(&conversation_address, ModerationAction::MoveToCategory(category_address))?;
+    Ok(())
+}
+
+/// Categories in order with their conversations, followed by the conversations not in any category
+pub fn handle_get_conversation_tree(community: Option<Address>) -> ZomeApiResult<ConversationTree> {
+    let mut categorized = HashSet::new();
+    let categories = get_categories(&community)?
+        .into_iter()
+        .map(|category| {
+            let conversations = get_category_conversations(&category.address)?;
+            categorized.extend(conversations.iter().map(|c| c.address.clone()));
+            Ok(CategoryNode {
+                address: category.address,
+                category: category.entry,
+                conversations,
+            })
+        })
+        .collect::<ZomeApiResult<Vec<CategoryNode>>>()?;
+    let uncategorized = handle_get_all_public_conversations(community)?
+        .into_iter()
+        .filter(|conversation| !categorized.contains(&conversation.address))
+        .collect();
+    Ok(ConversationTree { categories, uncategorized })
+}
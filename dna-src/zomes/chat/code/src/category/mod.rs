@@ -0,0 +1,125 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::{dna::entry_types::Sharing, validation::EntryValidationData},
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+use crate::conversation::{validate_moderator, Conversation};
+use crate::community::validate_admin;
+use crate::utils::{header_time, link_sources_and_time, GetLinksLoadResult};
+
+pub mod handlers;
+
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// A named group of conversations. Categories are listed in order of their position.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct Category {
+    pub name: String,
+    pub position: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub community: Option<Address>,
+    // agents who can rename and reorder the category
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moderators: Vec<Address>,
+}
+
+impl Category {
+    pub fn validate_name(&self) -> Result<(), String> {
+        let length = self.name.chars().count();
+        if length == 0 || length > MAX_NAME_LENGTH {
+            Err(format!("Category names must have 1-{} characters", MAX_NAME_LENGTH))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn is_moderated_by(&self, agent_address: &Address) -> bool {
+        self.moderators.contains(agent_address)
+    }
+}
+
+/// A category along with the conversations in it
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct CategoryNode {
+    pub address: Address,
+    pub category: Category,
+    pub conversations: Vec<GetLinksLoadResult<Conversation>>,
+}
+
+/// Every public conversation of a community, or outside any community, grouped by category
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct ConversationTree {
+    pub categories: Vec<CategoryNode>,
+    pub uncategorized: Vec<GetLinksLoadResult<Conversation>>,
+}
+
+use crate::{CATEGORY_ENTRY, IN_CATEGORY_LINK_TYPE};
+
+pub fn category_definition() -> ValidatingEntryType {
+    entry!(
+        name: CATEGORY_ENTRY,
+        description: "A group of conversations",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<Category>| {
+            match validation_data {
+                // only admins create the categories of a community, any agent can create a global one
+                EntryValidationData::Create{entry, validation_data} => {
+                    entry.validate_name()?;
+                    let sources = validation_data.sources();
+                    if !entry.moderators.iter().all(|moderator| sources.contains(moderator)) {
+                        return Err("A new category can only be moderated by its creator".into());
+                    }
+                    match &entry.community {
+                        Some(community_address) => {
+                            validate_admin(community_address, &sources, header_time(&validation_data)?)
+                        },
+                        None => Ok(()),
+                    }
+                },
+                // moderators and community admins can rename and reorder a category but not move it or hand it over
+                EntryValidationData::Modify{new_entry, old_entry, validation_data, ..} => {
+                    new_entry.validate_name()?;
+                    if new_entry.community != old_entry.community || new_entry.moderators != old_entry.moderators {
+                        return Err("Only the name and position of a category can be changed".into());
+                    }
+                    let sources = validation_data.sources();
+                    if sources.iter().any(|source| old_entry.is_moderated_by(source)) {
+                        return Ok(());
+                    }
+                    match &old_entry.community {
+                        Some(community_address) => {
+                            validate_admin(community_address, &sources, header_time(&validation_data)?)
+                        },
+                        None => Err("Only moderators of the category can change it".into()),
+                    }
+                },
+                _ => Err("Cannot delete a category".into()),
+            }
+        },
+
+        links: [
+            to!(
+                "public_conversation",
+                link_type: IN_CATEGORY_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                // only moderators of a conversation decide which category it goes in
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources, at) = link_sources_and_time(validation_data)?;
+                    validate_moderator(link.target(), &sources, at)
+                }
+            )
+        ]
+    )
+}
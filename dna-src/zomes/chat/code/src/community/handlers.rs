@@ -0,0 +1,152 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_json_api::json::RawString,
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+    AGENT_ADDRESS,
+};
+use std::{collections::HashSet, convert::TryFrom};
+use crate::community::{is_admin, is_member, Community};
+use crate::utils::{get_links_and_load_type, GetLinksLoadResult};
+use crate::{COMMUNITY_ENTRY, COMMUNITY_LINK_TYPE, COMMUNITY_MEMBER_LINK_TYPE, MEMBER_OF_COMMUNITY_LINK_TYPE};
+
+fn communities_anchor() -> Entry {
+    Entry::App("anchor".into(), RawString::from("communities").into())
+}
+
+pub fn handle_create_community(name: String, description: String) -> ZomeApiResult<Address> {
+    let community = Community {
+        name,
+        description,
+        admins: vec![AGENT_ADDRESS.to_string().into()],
+    };
+    let community_address =
+        hdk::commit_entry(&Entry::App(COMMUNITY_ENTRY.into(), community.into()))?;
+    let anchor_address = hdk::commit_entry(&communities_anchor())?;
+    hdk::link_entries(&anchor_address, &community_address, COMMUNITY_LINK_TYPE, "")?;
+    handle_join_community(community_address.clone())?;
+    Ok(community_address)
+}
+
+pub fn handle_join_community(community_address: Address) -> ZomeApiResult<()> {
+    if is_member(&community_address, &AGENT_ADDRESS)? {
+        return Ok(());
+    }
+    hdk::link_entries(&community_address, &AGENT_ADDRESS, COMMUNITY_MEMBER_LINK_TYPE, "")?;
+    hdk::link_entries(&AGENT_ADDRESS, &community_address, MEMBER_OF_COMMUNITY_LINK_TYPE, "")?;
+    Ok(())
+}
+
+pub fn handle_get_all_communities() -> ZomeApiResult<Vec<GetLinksLoadResult<Community>>> {
+    let anchor_address = hdk::entry_address(&communities_anchor())?;
+    let mut result = get_links_and_load_type(
+        &anchor_address,
+        LinkMatch::Exactly(COMMUNITY_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    result.retain(|e| uniques.insert(e.address.clone()));
+    Ok(result)
+}
+
+pub fn handle_get_my_communities() -> ZomeApiResult<Vec<GetLinksLoadResult<Community>>> {
+    let mut result = get_links_and_load_type(
+        &AGENT_ADDRESS,
+        LinkMatch::Exactly(MEMBER_OF_COMMUNITY_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    result.retain(|e| uniques.insert(e.address.clone()));
+    Ok(result)
+}
+
+/// The member directory of a community
+pub fn handle_get_community_members(community_address: Address) -> ZomeApiResult<Vec<Address>> {
+    let mut members = hdk::get_links(
+        &community_address,
+        LinkMatch::Exactly(COMMUNITY_MEMBER_LINK_TYPE),
+        LinkMatch::Any,
+    )?
+    .addresses();
+    let mut uniques = HashSet::new();
+    members.retain(|member| uniques.insert(member.clone()));
+    Ok(members)
+}
+
+fn require_admin(community_address: &Address) -> ZomeApiResult<()> {
+    if is_admin(community_address, &AGENT_ADDRESS)? {
+        Ok(())
+    } else {
+        Err(ZomeApiError::Internal("Only admins of the community can do this".into()))
+    }
+}
+
+/// The latest version of a community along with the address it is stored at
+fn latest_community(community_address: &Address) -> ZomeApiResult<(Address, Community)> {
+    let latest_entry = hdk::get_entry(community_address)?
+        .ok_or_else(|| ZomeApiError::Internal("Community could not be found".into()))?;
+    match &latest_entry {
+        Entry::App(_, entry_value) => Ok((
+            latest_entry.address(),
+            Community::try_from(entry_value.clone())
+                .map_err(|_| ZomeApiError::Internal("Invalid community data".into()))?,
+        )),
+        _ => Err(ZomeApiError::Internal("Community could not be found".into())),
+    }
+}
+
+/// Make a member of the community an admin. Only admins can do this.
+pub fn handle_add_community_admin(community_address: Address, agent_address: Address) -> ZomeApiResult<()> {
+    require_admin(&community_address)?;
+    if !is_member(&community_address, &agent_address)? {
+        return Err(ZomeApiError::Internal("Only members of the community can be made admins".into()));
+    }
+    let (latest_address, mut community) = latest_community(&community_address)?;
+    if !community.admins.contains(&agent_address) {
+        community.admins.push(agent_address);
+        hdk::update_entry(
+            Entry::App(COMMUNITY_ENTRY.into(), community.into()),
+            &latest_address,
+        )?;
+    }
+    Ok(())
+}
+
+/// Take an admin's role away, leaving them a member. Only admins can do this and
+/// a community always keeps at least one admin.
+pub fn handle_remove_community_admin(community_address: Address, agent_address: Address) -> ZomeApiResult<()> {
+    require_admin(&community_address)?;
+    let (latest_address, mut community) = latest_community(&community_address)?;
+    if !community.admins.contains(&agent_address) {
+        return Ok(());
+    }
+    community.admins.retain(|admin| admin != &agent_address);
+    if community.admins.is_empty() {
+        return Err(ZomeApiError::Internal("A community needs at least one admin".into()));
+    }
+    hdk::update_entry(
+        Entry::App(COMMUNITY_ENTRY.into(), community.into()),
+        &latest_address,
+    )?;
+    Ok(())
+}
+
+/// Remove a member from the community. Only admins can do this, and admins have to be
+/// demoted before they can be removed.
+pub fn handle_remove_community_member(community_address: Address, agent_address: Address) -> ZomeApiResult<()> {
+    require_admin(&community_address)?;
+    if is_admin(&community_address, &agent_address)? {
+        return Err(ZomeApiError::Internal("Admins must be demoted before they are removed".into()));
+    }
+    for link in hdk::get_links(&community_address, LinkMatch::Exactly(COMMUNITY_MEMBER_LINK_TYPE), LinkMatch::Any)?.links() {
+        if link.address == agent_address {
+            hdk::remove_link(&community_address, &agent_address, COMMUNITY_MEMBER_LINK_TYPE, link.tag.as_str())?;
+        }
+    }
+    for link in hdk::get_links(&agent_address, LinkMatch::Exactly(MEMBER_OF_COMMUNITY_LINK_TYPE), LinkMatch::Any)?.links() {
+        if link.address == community_address {
+            hdk::remove_link(&agent_address, &community_address, MEMBER_OF_COMMUNITY_LINK_TYPE, link.tag.as_str())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,274 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    error::ZomeApiResult,
+    holochain_core_types::{
+        chain_header::ChainHeader, dna::entry_types::Sharing, entry::Entry, link::LinkMatch,
+        validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::{JsonString, RawString}},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+
+use crate::category::Category;
+use crate::conversation::Conversation;
+use crate::utils::{author_chain, entry_at, header_time, link_and_sources, parse_iso8601_millis};
+
+pub mod handlers;
+
+/// A team with its own conversations, categories and members, so several can share
+/// one instance of the DNA. Admins moderate every conversation in the community.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct Community {
+    pub name: String,
+    pub description: String,
+    pub admins: Vec<Address>,
+}
+
+/// Whether the agent is an admin of the community at the given address
+pub fn is_admin(community_address: &Address, agent_address: &Address) -> ZomeApiResult<bool> {
+    let community: Community = get_as_type(community_address.clone())?;
+    Ok(community.admins.contains(agent_address))
+}
+
+/// Whether the agent was an admin of the community at the given time, for validation
+pub fn was_admin(community_address: &Address, agent_address: &Address, at: u64) -> bool {
+    entry_at::<Community>(community_address, at)
+        .ok()
+        .and_then(|community| community)
+        .map(|community| community.admins.contains(agent_address))
+        .unwrap_or(false)
+}
+
+/// Whether the agent has joined the community at the given address
+pub fn is_member(community_address: &Address, agent_address: &Address) -> ZomeApiResult<bool> {
+    Ok(hdk::get_links(community_address, LinkMatch::Exactly(COMMUNITY_MEMBER_LINK_TYPE), LinkMatch::Any)?
+        .addresses()
+        .contains(agent_address))
+}
+
+/// Validation helper that passes if any of the sources was an admin of the community at the given time
+pub fn validate_admin(community_address: &Address, sources: &[Address], at: u64) -> Result<(), String> {
+    if sources.iter().any(|agent_address| was_admin(community_address, agent_address, at)) {
+        Ok(())
+    } else {
+        Err("Only admins of the community can do this".into())
+    }
+}
+
+/// Validation helper that passes if any of the sources had joined the community at the given time,
+/// going by the membership links they added and removed on their own chain, and is still linked
+/// from the community. Removals by an admin aren't on the member's chain, so they are checked
+/// against the community's member links, which count as of when validation runs.
+fn validate_member(
+    community_address: &Address,
+    sources: &[Address],
+    at: u64,
+    author_chain: &[(ChainHeader, Entry)],
+) -> Result<(), String> {
+    let mut changes: Vec<(u64, &Address, bool)> = author_chain
+        .iter()
+        .filter_map(|(header, entry)| {
+            let (link, joined) = match entry {
+                Entry::LinkAdd(link_data) => (&link_data.link, true),
+                Entry::LinkRemove((link_data, _)) => (&link_data.link, false),
+                _ => return None,
+            };
+            if link.base() != community_address || link.link_type() != COMMUNITY_MEMBER_LINK_TYPE {
+                return None;
+            }
+            let changed_at = parse_iso8601_millis(&header.timestamp().to_string())?;
+            Some((changed_at, link.target(), joined))
+        })
+        .filter(|(changed_at, _, _)| *changed_at <= at)
+        .collect();
+    changes.sort_by_key(|(changed_at, _, _)| *changed_at);
+    let joined = |agent_address: &Address| {
+        changes
+            .iter()
+            .filter(|(_, member, _)| *member == agent_address)
+            .last()
+            .map(|(_, _, joined)| *joined)
+            .unwrap_or(false)
+    };
+    let still_linked = hdk::get_links(community_address, LinkMatch::Exactly(COMMUNITY_MEMBER_LINK_TYPE), LinkMatch::Any)
+        .map_err(|_| String::from("Community members could not be loaded"))?
+        .addresses();
+    if sources.iter().any(|source| joined(source) && still_linked.contains(source)) {
+        Ok(())
+    } else {
+        Err("Only members of a community can start conversations in it".into())
+    }
+}
+
+/// The entry that lists are linked from. Listings outside any community hang off
+/// the global anchor with the given name, those in a community off the community itself.
+pub fn scope_address(community: &Option<Address>, anchor: &str) -> ZomeApiResult<Address> {
+    match community {
+        Some(community_address) => Ok(community_address.clone()),
+        None => hdk::entry_address(&scope_anchor(anchor)),
+    }
+}
+
+/// Like `scope_address`, committing the global anchor so it can be linked from
+pub fn commit_scope(community: &Option<Address>, anchor: &str) -> ZomeApiResult<Address> {
+    match community {
+        Some(community_address) => Ok(community_address.clone()),
+        None => hdk::commit_entry(&scope_anchor(anchor)),
+    }
+}
+
+fn scope_anchor(anchor: &str) -> Entry {
+    Entry::App("anchor".into(), RawString::from(anchor).into())
+}
+
+use crate::{
+    CATEGORY_LINK_TYPE, COMMUNITY_ENTRY, COMMUNITY_MEMBER_LINK_TYPE, MEMBER_OF_COMMUNITY_LINK_TYPE,
+};
+
+pub fn community_definition() -> ValidatingEntryType {
+    entry!(
+        name: COMMUNITY_ENTRY,
+        description: "A team hosting its own conversations and members",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<Community>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    let sources = validation_data.sources();
+                    if !entry.admins.is_empty() && entry.admins.iter().all(|admin| sources.contains(admin)) {
+                        Ok(())
+                    } else {
+                        Err("A new community must be administered by its creator".into())
+                    }
+                },
+                EntryValidationData::Modify{new_entry, old_entry, validation_data, ..} => {
+                    if new_entry.admins.is_empty() {
+                        return Err("A community needs at least one admin".into());
+                    }
+                    let sources = validation_data.sources();
+                    if old_entry.admins.iter().any(|admin| sources.contains(admin)) {
+                        Ok(())
+                    } else {
+                        Err("Only admins of the community can change it".into())
+                    }
+                },
+                _ => Err("Cannot delete a community".into()),
+            }
+        },
+
+        links: [
+            to!(
+                "public_conversation",
+                link_type: "public_conversation",
+
+                // the author's chain is needed to check they had joined the community
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::ChainFull
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            let conversation: Conversation = get_as_type(link.link.target().clone())
+                                .map_err(|_| String::from("Conversation could not be found"))?;
+                            if conversation.community.as_ref() != Some(link.link.base()) {
+                                return Err("Conversation belongs to a different community".into());
+                            }
+                            validate_member(
+                                link.link.base(),
+                                &validation_data.sources(),
+                                header_time(&validation_data)?,
+                                &author_chain(&validation_data),
+                            )
+                        },
+                        hdk::LinkValidationData::LinkRemove { .. } => {
+                            Err("Conversations cannot be taken out of their community".into())
+                        },
+                    }
+                }
+            ),
+            to!(
+                "category",
+                link_type: CATEGORY_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, _) = link_and_sources(validation_data);
+                    let category: Category = get_as_type(link.target().clone())
+                        .map_err(|_| String::from("Category could not be found"))?;
+                    if category.community.as_ref() == Some(link.base()) {
+                        Ok(())
+                    } else {
+                        Err("Category belongs to a different community".into())
+                    }
+                }
+            ),
+            to!(
+                "%agent_id",
+                link_type: COMMUNITY_MEMBER_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                // agents join by themselves, admins can remove them
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            if validation_data.sources().contains(link.link.target()) {
+                                Ok(())
+                            } else {
+                                Err("Agents can only add themselves to a community".into())
+                            }
+                        },
+                        hdk::LinkValidationData::LinkRemove { link, validation_data } => {
+                            let sources = validation_data.sources();
+                            if sources.contains(link.link.target()) {
+                                Ok(())
+                            } else {
+                                validate_admin(link.link.base(), &sources, header_time(&validation_data)?)
+                            }
+                        },
+                    }
+                }
+            ),
+            from!(
+                "%agent_id",
+                link_type: MEMBER_OF_COMMUNITY_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            if validation_data.sources().contains(link.link.base()) {
+                                Ok(())
+                            } else {
+                                Err("Agents can only link themselves to a community".into())
+                            }
+                        },
+                        hdk::LinkValidationData::LinkRemove { link, validation_data } => {
+                            let sources = validation_data.sources();
+                            if sources.contains(link.link.base()) {
+                                Ok(())
+                            } else {
+                                validate_admin(link.link.target(), &sources, header_time(&validation_data)?)
+                            }
+                        },
+                    }
+                }
+            )
+        ]
+    )
+}
@@ -1,48 +1,64 @@
 use hdk::{
     self,
-    error::ZomeApiResult,
+    error::{ZomeApiError, ZomeApiResult},
     holochain_core_types::{entry::Entry, link::{LinkMatch, link_data::LinkData, LinkActionKind}},
-    holochain_json_api::json::{JsonString, RawString},
+    holochain_json_api::json::JsonString,
     holochain_persistence_api::cas::content::{Address, AddressableContent},
     AGENT_ADDRESS,
     prelude::{QueryResult, QueryArgsOptions},
+    utils::get_as_type,
 
 };
-use std::collections::HashSet;
+use std::{collections::HashSet, convert::TryFrom};
 use crate::{
     DirectMessage,
     NotificationSignalPayload,
     JoinChannelSignalPayload,
+    MEMBER_OF_LINK_TYPE,
+    MENTIONS_LINK_TYPE,
     MESSAGE_ENTRY,
+    MESSAGE_LINK_TYPE_TO,
+    PINNED_LINK_TYPE,
+    PUBLIC_STREAM_ENTRY,
     PUBLIC_STREAM_LINK_TYPE_TO,
     signal_ui,
 };
-use crate::conversation::Conversation;
+use crate::audit::{handlers::record_moderation, ModerationAction};
+use crate::conversation::{
+    is_moderator, validate_posting_policy, Conversation, JoinPolicy, MembershipProof, PostingPolicy,
+};
+use crate::activity;
+use crate::community::handlers::handle_get_community_members;
+use crate::block;
+use crate::community;
+use crate::hashtag;
+use crate::join_request;
 use crate::message;
-use crate::utils::{get_links_and_load_type, GetLinksLoadResult};
+use crate::utils::{get_links_and_load_type, query_local_links, GetLinksLoadResult};
 
 
 
 
-fn notify_conversation(conversation_address: Address, message: DirectMessage) -> ZomeApiResult<()> {
-    handle_get_members(conversation_address.clone())?
-        .iter()
-        .for_each(|member_id| {
-            if member_id == &Address::from(AGENT_ADDRESS.to_string()) { // don't waste resources and just trigger a signal directly
-                signal_ui(&message);
-            } else {
-                hdk::debug(format!("Send a message to: {:?}", &member_id.to_string())).ok();
-                hdk::send(
-                    member_id.clone(),
-                    JsonString::from(
-                        message.clone()
-                    ).into(),
-                    1.into(),
-                )
-                .ok();
-            }
+pub(crate) fn notify_agent(agent_id: &Address, message: &DirectMessage) {
+    if agent_id == &Address::from(AGENT_ADDRESS.to_string()) { // don't waste resources and just trigger a signal directly
+        signal_ui(message);
+    } else {
+        hdk::debug(format!("Send a message to: {:?}", &agent_id.to_string())).ok();
+        hdk::send(
+            agent_id.clone(),
+            JsonString::from(
+                message.clone()
+            ).into(),
+            1.into(),
+        )
+        .ok();
+    }
+}
 
-        });
+pub(crate) fn notify_conversation(conversation_address: Address, message: DirectMessage) -> ZomeApiResult<()> {
+    handle_get_members(conversation_address.clone(), None)?
+        .iter()
+        .for_each(|member_id| notify_agent(member_id, &message));
     Ok(())
 }
 
@@ -60,6 +76,20 @@ fn notify_conversation_message(conversation_address: Address, message: message::
     notify_conversation(conversation_address, message)
 }
 
+/// Mentions are sent straight to each mentioned agent rather than through the conversation
+fn notify_mentions(conversation_address: Address, message_address: Address, message: message::Message) {
+    let notification = DirectMessage::MentionNotification(
+        NotificationSignalPayload{
+            conversation_address,
+            message_address,
+            message: message.clone(),
+        }
+    );
+    message.mentions
+        .iter()
+        .for_each(|agent_id| notify_agent(agent_id, &notification));
+}
+
 fn notify_conversation_join(conversation_address: Address) -> ZomeApiResult<()> {
     let message = DirectMessage::JoinChannelNotification(
         JoinChannelSignalPayload{
@@ -73,17 +103,31 @@ fn notify_conversation_join(conversation_address: Address) -> ZomeApiResult<()>
 pub fn handle_start_conversation(
     name: String,
     description: String,
+    community: Option<Address>,
 ) -> ZomeApiResult<Address> {
-    let conversation = Conversation { name, description };
+    if let Some(community_address) = &community {
+        if !community::is_member(community_address, &AGENT_ADDRESS)? {
+            return Err(ZomeApiError::Internal(
+                "Only members of a community can start conversations in it".into(),
+            ));
+        }
+    }
+    let conversation = Conversation {
+        name,
+        description,
+        moderators: vec![AGENT_ADDRESS.to_string().into()],
+        default_ttl: None,
+        banned: Vec::new(),
+        posting_policy: PostingPolicy::default(),
+        join_policy: JoinPolicy::default(),
+        community: community.clone(),
+        tags: Vec::new(),
+    };
     let entry = Entry::App("public_conversation".into(), conversation.into());
     let conversation_address = hdk::commit_entry(&entry)?;
-    let anchor_entry = Entry::App(
-        "anchor".into(),
-        RawString::from("public_conversations").into(),
-    );
-    let anchor_address = hdk::commit_entry(&anchor_entry)?;
+    let list_address = community::commit_scope(&community, "public_conversations")?;
     hdk::link_entries(
-        &anchor_address,
+        &list_address,
         &conversation_address,
         "public_conversation",
         "",
@@ -106,6 +150,7 @@ fn entry_is_link_between(entry: &Entry, base: &Address, target: &Address) -> boo
 }
 
 /// An agent is a member of a channel if the have created a link between it and themselves in their local chain
+/// and a moderator has not since kicked them out
 fn agent_is_member_of_channel(agent_addr: &Address, conversation_address: &Address) -> ZomeApiResult<bool> {
     if let QueryResult::Entries(results) = hdk::query_result(
         "%link_add".into(),
@@ -113,6 +158,7 @@ fn agent_is_member_of_channel(agent_addr: &Address, conversation_address: &Addre
     )? {
         Ok(
             results.iter().any(|(_, entry)| entry_is_link_between(entry, conversation_address, agent_addr))
+                && handle_get_members(conversation_address.clone(), None)?.contains(agent_addr)
         )
     } else {
         unreachable!()
@@ -120,12 +166,37 @@ fn agent_is_member_of_channel(agent_addr: &Address, conversation_address: &Addre
 }
 
 pub fn handle_join_conversation(conversation_address: Address) -> ZomeApiResult<()> {
+    let conversation: Conversation = get_as_type(conversation_address.clone())?;
+    match conversation.join_policy {
+        JoinPolicy::Open => join(conversation_address, ""),
+        _ if is_moderator(&conversation_address, &AGENT_ADDRESS)? => join(conversation_address, ""),
+        JoinPolicy::InviteOnly => Err(ZomeApiError::Internal(
+            "An invite is needed to join this conversation".into(),
+        )),
+        // ask to join instead, the agent becomes a member once a moderator approves
+        JoinPolicy::Approval => join_request::handlers::join_when_approved(conversation_address),
+    }
+}
+
+/// Join a conversation that isn't open, recording why this agent was let in
+pub(crate) fn join_with_proof(conversation_address: Address, proof: MembershipProof) -> ZomeApiResult<()> {
+    join(conversation_address, proof.to_tag().as_str())
+}
+
+fn join(conversation_address: Address, tag: &str) -> ZomeApiResult<()> {
     if !agent_is_member_of_channel(&AGENT_ADDRESS, &conversation_address)? {
         hdk::debug("Joining channel!").ok();
         hdk::link_entries(
             &conversation_address,
             &AGENT_ADDRESS,
             PUBLIC_STREAM_LINK_TYPE_TO,
+            tag,
+        )?;
+        // back-link so the agent's conversations can be listed without asking every conversation
+        hdk::link_entries(
+            &AGENT_ADDRESS,
+            &conversation_address,
+            MEMBER_OF_LINK_TYPE,
             "",
         )?;
         notify_conversation_join(conversation_address)?;
@@ -135,42 +206,193 @@ pub fn handle_join_conversation(conversation_address: Address) -> ZomeApiResult<
     Ok(())
 }
 
-pub fn handle_get_members(address: Address) -> ZomeApiResult<Vec<Address>> {
-    let all_member_ids =
+/// Members of a conversation. Given a community, only those who are still members of it are listed.
+pub fn handle_get_members(address: Address, community: Option<Address>) -> ZomeApiResult<Vec<Address>> {
+    let mut all_member_ids =
         hdk::get_links(&address, LinkMatch::Exactly("has_member"), LinkMatch::Any)?
             .addresses()
             .to_owned();
+    if let Some(community_address) = community {
+        let community_members: HashSet<Address> =
+            handle_get_community_members(community_address)?.into_iter().collect();
+        all_member_ids.retain(|member| community_members.contains(member));
+    }
     Ok(all_member_ids)
 }
 
+/// Conversations the given agent has joined in the given community, or outside any community,
+/// found through their `member_of` back-links
+pub fn handle_get_member_conversations(
+    agent_address: Address,
+    community: Option<Address>,
+) -> ZomeApiResult<Vec<GetLinksLoadResult<Conversation>>> {
+    let mut result = get_links_and_load_type(
+        &agent_address,
+        LinkMatch::Exactly(MEMBER_OF_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    result.retain(|e| e.entry.community == community && uniques.insert(e.address.clone()));
+    Ok(result)
+}
+
+pub fn handle_get_my_conversations(community: Option<Address>) -> ZomeApiResult<Vec<GetLinksLoadResult<Conversation>>> {
+    handle_get_member_conversations(AGENT_ADDRESS.to_string().into(), community)
+}
+
+/// Messages posted to a conversation. Messages that have expired as of `now` are left out,
+/// as are messages from blocked agents if `exclude_blocked` is set.
 pub fn handle_get_messages(
     address: Address,
+    now: Option<u64>,
+    exclude_blocked: bool,
 ) -> ZomeApiResult<Vec<GetLinksLoadResult<message::Message>>> {
-    get_links_and_load_type(&address, LinkMatch::Exactly("message_in"), LinkMatch::Any)
+    let mut result =
+        get_links_and_load_type(&address, LinkMatch::Exactly("message_in"), LinkMatch::Any)?;
+    if let Some(now) = now {
+        result.retain(|e| !e.entry.is_expired(now));
+    }
+    if exclude_blocked {
+        let blocked: HashSet<String> = block::handlers::handle_get_blocked_agents()?
+            .iter()
+            .map(|agent_address| agent_address.to_string())
+            .collect();
+        result.retain(|e| !blocked.contains(&e.entry.author));
+    }
+    Ok(result)
+}
+
+/// Remove this agent's messages in the conversation that have expired as of `now`.
+/// Returns the addresses of the removed messages.
+pub fn handle_cleanup_expired_messages(
+    conversation_address: Address,
+    now: u64,
+) -> ZomeApiResult<Vec<Address>> {
+    let my_address = AGENT_ADDRESS.to_string();
+    let mut removed = Vec::new();
+    for link in hdk::get_links(
+        &conversation_address,
+        LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+        LinkMatch::Any,
+    )?
+    .links()
+    {
+        let message: message::Message = match get_as_type(link.address.clone()) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        if message.author == my_address && message.is_expired(now) {
+            hdk::remove_link(&conversation_address, &link.address, MESSAGE_LINK_TYPE_TO, link.tag.as_str())?;
+            activity::handlers::record_activity(&conversation_address)?;
+            if !removed.contains(&link.address) {
+                hashtag::handlers::unindex_message_hashtags(&link.address, &message)?;
+                hdk::remove_entry(&link.address)?;
+                removed.push(link.address);
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Load a message being quoted or forwarded so it can be attributed to its original author
+fn resolve_reference(
+    kind: message::ReferenceKind,
+    source: message::MessageSource,
+) -> ZomeApiResult<(message::MessageReference, message::Message)> {
+    let original: message::Message = get_as_type(source.message_address.clone())
+        .map_err(|_| ZomeApiError::Internal("Referenced message could not be found".into()))?;
+    Ok((message::MessageReference::new(kind, source, &original), original))
 }
 
 pub fn handle_post_message(
     conversation_address: Address,
     message_spec: message::MessageSpec,
 ) -> ZomeApiResult<()> {
-    let message = message::Message::from_spec(&message_spec, &AGENT_ADDRESS.to_string());
+    let reference = match message_spec.quote.clone() {
+        Some(source) => Some(resolve_reference(message::ReferenceKind::Quote, source)?.0),
+        None => None,
+    };
+    post_message(conversation_address, message_spec, reference)
+}
+
+/// Post a copy of a message to another conversation, crediting the original author and time.
+/// Mentions are not carried over so nobody is notified twice.
+pub fn handle_forward_message(
+    message_address: Address,
+    source_conversation: Address,
+    target_conversation: Address,
+    timestamp: u64,
+) -> ZomeApiResult<()> {
+    let (reference, original) = resolve_reference(
+        message::ReferenceKind::Forward,
+        message::MessageSource {
+            conversation_address: source_conversation,
+            message_address,
+        },
+    )?;
+    let message_spec = message::MessageSpec {
+        message_type: original.message_type,
+        timestamp,
+        payload: original.payload,
+        meta: original.meta,
+        mentions: Vec::new(),
+        attachments: original.attachments,
+        expires_at: None,
+        quote: None,
+    };
+    post_message(target_conversation, message_spec, Some(reference))
+}
+
+fn post_message(
+    conversation_address: Address,
+    message_spec: message::MessageSpec,
+    reference: Option<message::MessageReference>,
+) -> ZomeApiResult<()> {
+    let conversation: Conversation = get_as_type(conversation_address.clone())?;
+    let mut message = message::Message::from_spec(&message_spec, &AGENT_ADDRESS.to_string());
+    message.reference = reference;
+    if message.expires_at.is_none() {
+        message.expires_at = conversation.default_ttl.map(|ttl| message.timestamp.saturating_add(ttl));
+    }
     let message_entry = Entry::App("message".into(), message.clone().into());
+    // check the policy up front so a message that can't be linked is never committed.
+    // Slow mode only delays a message, anything else means it can never be posted.
+    // the message timestamp stands in for the header time the link will get
+    validate_posting_policy(
+        &conversation_address,
+        &conversation,
+        &message_entry.address(),
+        &[AGENT_ADDRESS.to_string().into()],
+        message.timestamp,
+        &query_local_links()?,
+    )
+    .map_err(|reason| {
+        if conversation.posting_policy.announce {
+            ZomeApiError::ValidationFailed(reason)
+        } else {
+            ZomeApiError::Internal(reason)
+        }
+    })?;
     let message_addr = hdk::commit_entry(&message_entry)?;
     hdk::link_entries(&conversation_address, &message_addr, "message_in", "")?;
+    activity::handlers::record_activity(&conversation_address)?;
+    for agent_id in &message.mentions {
+        hdk::link_entries(agent_id, &message_addr, MENTIONS_LINK_TYPE, conversation_address.to_string().as_str())?;
+    }
+    hashtag::handlers::index_message_hashtags(&conversation_address, &conversation, &message_addr, &message)?;
     // send the message direct as a signal to every agent in the channel
-    notify_conversation_message(conversation_address, message)?;
+    notify_conversation_message(conversation_address.clone(), message.clone())?;
+    notify_mentions(conversation_address, message_addr, message);
     Ok(())
 }
 
-pub fn handle_get_all_public_conversations() -> ZomeApiResult<Vec<GetLinksLoadResult<Conversation>>>
-{
-    let anchor_entry = Entry::App(
-        "anchor".into(),
-        RawString::from("public_conversations").into(),
-    );
-    let anchor_address = hdk::entry_address(&anchor_entry)?;
+/// Conversations of a community, or those outside any community if none is given
+pub fn handle_get_all_public_conversations(
+    community: Option<Address>,
+) -> ZomeApiResult<Vec<GetLinksLoadResult<Conversation>>> {
+    let list_address = community::scope_address(&community, "public_conversations")?;
     let mut result = get_links_and_load_type(
-        &anchor_address,
+        &list_address,
         LinkMatch::Exactly("public_conversation"),
         LinkMatch::Any,
     )?;
@@ -180,3 +402,244 @@ pub fn handle_get_all_public_conversations() -> ZomeApiResult<Vec<GetLinksLoadRe
     result.retain(|e| uniques.insert(e.address.clone()));
     Ok(result)
 }
+
+pub(crate) fn require_moderator(conversation_address: &Address) -> ZomeApiResult<()> {
+    if is_moderator(conversation_address, &AGENT_ADDRESS)? {
+        Ok(())
+    } else {
+        Err(ZomeApiError::Internal(
+            "Only moderators of the conversation can do this".into(),
+        ))
+    }
+}
+
+/// Pin links are tagged with their position so pins can be returned in the order they were made
+fn get_pin_links(conversation_address: &Address) -> ZomeApiResult<Vec<(u64, Address)>> {
+    let mut pins: Vec<(u64, Address)> = hdk::get_links(
+        conversation_address,
+        LinkMatch::Exactly(PINNED_LINK_TYPE),
+        LinkMatch::Any,
+    )?
+    .links()
+    .into_iter()
+    .map(|link| (link.tag.parse().unwrap_or(0), link.address))
+    .collect();
+    pins.sort();
+    Ok(pins)
+}
+
+pub fn handle_pin_message(conversation_address: Address, message_address: Address) -> ZomeApiResult<()> {
+    require_moderator(&conversation_address)?;
+    let in_conversation = hdk::get_links(
+        &conversation_address,
+        LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+        LinkMatch::Any,
+    )?
+    .addresses()
+    .contains(&message_address);
+    if !in_conversation {
+        return Err(ZomeApiError::Internal(
+            "Message was not posted in this conversation".into(),
+        ));
+    }
+    let pins = get_pin_links(&conversation_address)?;
+    if pins.iter().any(|(_, address)| address == &message_address) {
+        return Ok(());
+    }
+    let position = pins.last().map(|(position, _)| position + 1).unwrap_or(0);
+    hdk::link_entries(
+        &conversation_address,
+        &message_address,
+        PINNED_LINK_TYPE,
+        position.to_string().as_str(),
+    )?;
+    record_moderation(&conversation_address, ModerationAction::Pin(message_address))?;
+    Ok(())
+}
+
+pub fn handle_unpin_message(conversation_address: Address, message_address: Address) -> ZomeApiResult<()> {
+    require_moderator(&conversation_address)?;
+    let mut unpinned = false;
+    for (position, address) in get_pin_links(&conversation_address)? {
+        if address == message_address {
+            hdk::remove_link(
+                &conversation_address,
+                &message_address,
+                PINNED_LINK_TYPE,
+                position.to_string().as_str(),
+            )?;
+            unpinned = true;
+        }
+    }
+    if unpinned {
+        record_moderation(&conversation_address, ModerationAction::Unpin(message_address))?;
+    }
+    Ok(())
+}
+
+/// Pinned messages of a conversation in the order they were pinned
+pub fn handle_get_pinned_messages(
+    conversation_address: Address,
+) -> ZomeApiResult<Vec<GetLinksLoadResult<message::Message>>> {
+    let mut uniques = HashSet::new();
+    Ok(get_pin_links(&conversation_address)?
+        .into_iter()
+        .filter(|(_, address)| uniques.insert(address.clone()))
+        .filter_map(|(_, address)| {
+            get_as_type(address.clone())
+                .ok()
+                .map(|entry| GetLinksLoadResult { entry, address })
+        })
+        .collect())
+}
+
+/// Apply a change to the settings of a conversation. Only moderators can do this.
+/// Links stay on the original address, reading it always gives the latest version.
+fn update_conversation<F: FnOnce(&mut Conversation)>(
+    conversation_address: &Address,
+    change: F,
+) -> ZomeApiResult<Address> {
+    require_moderator(conversation_address)?;
+    let latest_entry = hdk::get_entry(conversation_address)?
+        .ok_or_else(|| ZomeApiError::Internal("Conversation could not be found".into()))?;
+    let mut conversation: Conversation = match &latest_entry {
+        Entry::App(_, entry_value) => Conversation::try_from(entry_value.clone())
+            .map_err(|_| ZomeApiError::Internal("Invalid conversation data".into()))?,
+        _ => return Err(ZomeApiError::Internal("Conversation could not be found".into())),
+    };
+    change(&mut conversation);
+    hdk::update_entry(
+        Entry::App(PUBLIC_STREAM_ENTRY.into(), conversation.into()),
+        &latest_entry.address(),
+    )
+}
+
+pub fn handle_set_conversation_ttl(
+    conversation_address: Address,
+    default_ttl: Option<u64>,
+) -> ZomeApiResult<()> {
+    update_conversation(&conversation_address, |conversation| {
+        conversation.default_ttl = default_ttl;
+    })?;
+    record_moderation(&conversation_address, ModerationAction::SetTtl(default_ttl))?;
+    Ok(())
+}
+
+pub fn handle_set_posting_policy(
+    conversation_address: Address,
+    posting_policy: PostingPolicy,
+) -> ZomeApiResult<()> {
+    update_conversation(&conversation_address, |conversation| {
+        conversation.posting_policy = posting_policy.clone();
+    })?;
+    record_moderation(&conversation_address, ModerationAction::SetPostingPolicy(posting_policy))?;
+    Ok(())
+}
+
+pub fn handle_set_join_policy(
+    conversation_address: Address,
+    join_policy: JoinPolicy,
+) -> ZomeApiResult<()> {
+    update_conversation(&conversation_address, |conversation| {
+        conversation.join_policy = join_policy.clone();
+    })?;
+    record_moderation(&conversation_address, ModerationAction::SetJoinPolicy(join_policy))?;
+    Ok(())
+}
+
+/// Replace the tags a conversation can be found by
+pub fn handle_set_conversation_tags(
+    conversation_address: Address,
+    tags: Vec<String>,
+) -> ZomeApiResult<()> {
+    let mut uniques = HashSet::new();
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|tag| hashtag::normalize(tag))
+        .filter(|tag| uniques.insert(tag.clone()))
+        .collect();
+    update_conversation(&conversation_address, |conversation| {
+        conversation.tags = tags.clone();
+    })?;
+    record_moderation(&conversation_address, ModerationAction::SetTags(tags))?;
+    Ok(())
+}
+
+/// Change what a conversation is about
+pub fn handle_set_conversation_topic(
+    conversation_address: Address,
+    description: String,
+) -> ZomeApiResult<()> {
+    update_conversation(&conversation_address, |conversation| {
+        conversation.description = description.clone();
+    })?;
+    record_moderation(&conversation_address, ModerationAction::ChangeTopic(description))?;
+    Ok(())
+}
+
+/// Take a message out of a conversation. Only moderators can do this for other agents' messages.
+pub(crate) fn remove_message(conversation_address: &Address, message_address: &Address) -> ZomeApiResult<()> {
+    require_moderator(conversation_address)?;
+    for link in hdk::get_links(
+        conversation_address,
+        LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+        LinkMatch::Any,
+    )?
+    .links()
+    {
+        if &link.address == message_address {
+            hdk::remove_link(conversation_address, message_address, MESSAGE_LINK_TYPE_TO, link.tag.as_str())?;
+            activity::handlers::record_activity(conversation_address)?;
+        }
+    }
+    if let Ok(message) = get_as_type::<message::Message>(message_address.clone()) {
+        hashtag::handlers::unindex_message_hashtags(message_address, &message)?;
+    }
+    record_moderation(conversation_address, ModerationAction::DeleteMessage(message_address.clone()))?;
+    Ok(())
+}
+
+/// Stop an agent from joining or posting to a conversation, removing them if they are a member
+pub(crate) fn ban_member(conversation_address: &Address, agent_address: &Address) -> ZomeApiResult<()> {
+    update_conversation(conversation_address, |conversation| {
+        if !conversation.banned.contains(agent_address) {
+            conversation.banned.push(agent_address.clone());
+        }
+    })?;
+    remove_member(conversation_address, agent_address)?;
+    record_moderation(conversation_address, ModerationAction::Ban(agent_address.clone()))?;
+    Ok(())
+}
+
+pub fn handle_delete_message(conversation_address: Address, message_address: Address) -> ZomeApiResult<()> {
+    remove_message(&conversation_address, &message_address)
+}
+
+/// Remove the links between a conversation and one of its members
+fn remove_member(conversation_address: &Address, agent_address: &Address) -> ZomeApiResult<()> {
+    for link in hdk::get_links(
+        conversation_address,
+        LinkMatch::Exactly(PUBLIC_STREAM_LINK_TYPE_TO),
+        LinkMatch::Any,
+    )?
+    .links()
+    {
+        if &link.address == agent_address {
+            hdk::remove_link(conversation_address, agent_address, PUBLIC_STREAM_LINK_TYPE_TO, link.tag.as_str())?;
+        }
+    }
+    for link in hdk::get_links(agent_address, LinkMatch::Exactly(MEMBER_OF_LINK_TYPE), LinkMatch::Any)?.links() {
+        if &link.address == conversation_address {
+            hdk::remove_link(agent_address, conversation_address, MEMBER_OF_LINK_TYPE, link.tag.as_str())?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove an agent from a conversation's members. Unlike a ban they are free to join again.
+pub fn handle_kick_member(conversation_address: Address, agent_address: Address) -> ZomeApiResult<()> {
+    require_moderator(&conversation_address)?;
+    remove_member(&conversation_address, &agent_address)?;
+    record_moderation(&conversation_address, ModerationAction::Kick(agent_address))?;
+    Ok(())
+}
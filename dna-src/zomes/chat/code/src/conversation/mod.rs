@@ -1,20 +1,250 @@
 use hdk::{
     self,
     entry_definition::ValidatingEntryType,
-    holochain_core_types::dna::entry_types::Sharing,
+    error::ZomeApiResult,
+    holochain_core_types::{
+        chain_header::ChainHeader,
+        dna::entry_types::Sharing,
+        entry::Entry,
+        validation::{EntryValidationData, ValidationData},
+    },
     holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
 };
 
 pub mod handlers;
 
+/// Who may post to a conversation and how often
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PostingPolicy {
+    // only moderators can post
+    #[serde(default)]
+    pub announce: bool,
+    // members other than moderators can post once per this many milliseconds
+    #[serde(default)]
+    pub slow_mode: Option<u64>,
+}
+
+impl PostingPolicy {
+    pub fn is_open(&self) -> bool {
+        self == &PostingPolicy::default()
+    }
+}
+
+/// Who may become a member of a conversation. Moderators can always join.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinPolicy {
+    Open,
+    // agents need an invite created by a moderator
+    InviteOnly,
+    // agents ask to join and a moderator approves them
+    Approval,
+}
+
+impl Default for JoinPolicy {
+    fn default() -> JoinPolicy {
+        JoinPolicy::Open
+    }
+}
+
+impl JoinPolicy {
+    pub fn is_open(&self) -> bool {
+        self == &JoinPolicy::Open
+    }
+}
+
+/// Why an agent was allowed into a conversation that isn't open, carried in the tag
+/// of their `has_member` link. Agents who joined an open conversation have an empty tag.
+/// Only the address of an invite is recorded, never the token used to redeem it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipProof {
+    Invite { invite: Address, secret: String },
+    Approved { decision: Address },
+}
+
+impl MembershipProof {
+    pub fn from_tag(tag: &str) -> Option<MembershipProof> {
+        serde_json::from_str(tag).ok()
+    }
+
+    pub fn to_tag(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
 pub struct Conversation {
     pub name: String,
     pub description: String,
+    // conversations started before moderation existed have no moderators
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moderators: Vec<Address>,
+    // messages posted without an expiry expire this many milliseconds after their timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_ttl: Option<u64>,
+    // agents banned by a moderator can no longer join or post
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub banned: Vec<Address>,
+    #[serde(default, skip_serializing_if = "PostingPolicy::is_open")]
+    pub posting_policy: PostingPolicy,
+    #[serde(default, skip_serializing_if = "JoinPolicy::is_open")]
+    pub join_policy: JoinPolicy,
+    // conversations outside any community are listed globally
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub community: Option<Address>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+pub const MAX_TAGS: usize = 10;
+
+impl Conversation {
+    /// Checks the number and length of the tags a conversation can be discovered by
+    pub fn validate_tags(&self) -> Result<(), String> {
+        if self.tags.len() > MAX_TAGS {
+            return Err(format!("Conversations can have at most {} tags", MAX_TAGS));
+        }
+        if self.tags.iter().any(|tag| tag.is_empty() || tag.chars().count() > MAX_HASHTAG_LENGTH) {
+            return Err(format!("Tags must have 1-{} characters", MAX_HASHTAG_LENGTH));
+        }
+        Ok(())
+    }
+
+    /// Moderators of a conversation in a community include the community's admins
+    pub fn is_moderated_by(&self, agent_address: &Address) -> bool {
+        self.moderators.contains(agent_address)
+            || self.community
+                .as_ref()
+                .map(|community_address| is_admin(community_address, agent_address).unwrap_or(false))
+                .unwrap_or(false)
+    }
+
+    /// Like `is_moderated_by`, going by the community's admins at the given time for validation
+    pub fn was_moderated_by(&self, agent_address: &Address, at: u64) -> bool {
+        self.moderators.contains(agent_address)
+            || self.community
+                .as_ref()
+                .map(|community_address| was_admin(community_address, agent_address, at))
+                .unwrap_or(false)
+    }
+}
+
+/// The conversation as it stood at the given time, going by the headers of its versions
+pub fn conversation_at(conversation_address: &Address, at: u64) -> Result<Conversation, String> {
+    entry_at(conversation_address, at)
+        .map_err(|_| String::from("Conversation could not be found"))?
+        .ok_or_else(|| String::from("Conversation did not exist yet"))
+}
+
+/// Whether the agent moderates the conversation at the given address
+pub fn is_moderator(conversation_address: &Address, agent_address: &Address) -> ZomeApiResult<bool> {
+    let conversation: Conversation = get_as_type(conversation_address.clone())?;
+    Ok(conversation.is_moderated_by(agent_address))
+}
+
+/// Validation helper that fails if the agent is banned from the conversation
+fn validate_not_banned(conversation: &Conversation, agent_address: &Address) -> Result<(), String> {
+    if conversation.banned.contains(agent_address) {
+        Err("Agent is banned from this conversation".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that an agent being added to a conversation's members was allowed in
+/// by the conversation as it was when they joined
+fn validate_membership(
+    conversation_address: &Address,
+    agent_address: &Address,
+    tag: &str,
+    validation_data: &ValidationData,
+) -> Result<(), String> {
+    let joined_at = header_time(validation_data)?;
+    let conversation = conversation_at(conversation_address, joined_at)?;
+    validate_not_banned(&conversation, agent_address)?;
+    if conversation.join_policy.is_open() {
+        return Ok(());
+    }
+    if !validation_data.sources().contains(agent_address) {
+        return Err("Agents can only add themselves to this conversation".into());
+    }
+    if conversation.was_moderated_by(agent_address, joined_at) {
+        return Ok(());
+    }
+    match MembershipProof::from_tag(tag) {
+        Some(MembershipProof::Invite { invite, secret }) => {
+            validate_invite_use(conversation_address, agent_address, &invite, &secret, joined_at)
+        },
+        Some(MembershipProof::Approved { decision }) => {
+            validate_approval(conversation_address, agent_address, &decision)
+        },
+        None => Err("An invite or approval is needed to join this conversation".into()),
+    }
+}
+
+/// Validation helper that passes if any of the sources moderated the conversation at the given time
+pub fn validate_moderator(conversation_address: &Address, sources: &[Address], at: u64) -> Result<(), String> {
+    let conversation = conversation_at(conversation_address, at)?;
+    if sources.iter().any(|agent_address| conversation.was_moderated_by(agent_address, at)) {
+        Ok(())
+    } else {
+        Err("Only moderators of the conversation can do this".into())
+    }
+}
+
+/// Checks a message being posted at the given time against the given version of the conversation's
+/// posting policy. Slow mode only looks at when the author linked earlier messages to the conversation
+/// on their own chain, and takes the difference either way so backdating a message doesn't get around it.
+pub fn validate_posting_policy(
+    conversation_address: &Address,
+    conversation: &Conversation,
+    message_address: &Address,
+    sources: &[Address],
+    posted_at: u64,
+    author_chain: &[(ChainHeader, Entry)],
+) -> Result<(), String> {
+    let policy = &conversation.posting_policy;
+    if policy.is_open() || sources.iter().any(|source| conversation.was_moderated_by(source, posted_at)) {
+        return Ok(());
+    }
+    if policy.announce {
+        return Err("Only moderators can post in this conversation".into());
+    }
+    if let Some(interval) = policy.slow_mode {
+        let posted_recently = author_chain
+            .iter()
+            .filter_map(|(header, entry)| match entry {
+                Entry::LinkAdd(link_data)
+                    if link_data.link.base() == conversation_address
+                        && link_data.link.link_type() == MESSAGE_LINK_TYPE_TO
+                        && link_data.link.target() != message_address =>
+                {
+                    parse_iso8601_millis(&header.timestamp().to_string())
+                }
+                _ => None,
+            })
+            .any(|earlier| {
+                let difference = if earlier > posted_at { earlier - posted_at } else { posted_at - earlier };
+                difference < interval
+            });
+        if posted_recently {
+            return Err(format!("Members can only post once every {} milliseconds", interval));
+        }
+    }
+    Ok(())
 }
 
 use crate::{
-    MESSAGE_LINK_TYPE_TO, PUBLIC_STREAM_ENTRY,
+    community::{is_admin, was_admin},
+    hashtag::MAX_HASHTAG_LENGTH,
+    invite::validate_invite_use,
+    join_request::validate_approval,
+    message::Message,
+    utils::{author_chain, entry_at, header_time, link_sources_and_time, parse_iso8601_millis},
+    MEMBER_OF_LINK_TYPE, MESSAGE_LINK_TYPE_TO, PINNED_LINK_TYPE, PUBLIC_STREAM_ENTRY,
     PUBLIC_STREAM_LINK_TYPE_TO,
 };
 
@@ -28,8 +258,31 @@ pub fn public_conversation_definition() -> ValidatingEntryType {
             hdk::ValidationPackageDefinition::Entry
         },
 
-        validation: |_validation_data: hdk::EntryValidationData<Conversation>| {
-            Ok(())
+        validation: |validation_data: hdk::EntryValidationData<Conversation>| {
+            match validation_data {
+                // community membership is checked when the conversation is listed in the community
+                EntryValidationData::Create{entry, validation_data} => {
+                    // the creator can only make themselves a moderator
+                    entry.validate_tags()?;
+                    let sources = validation_data.sources();
+                    if entry.moderators.iter().all(|moderator| sources.contains(moderator)) {
+                        Ok(())
+                    } else {
+                        Err("A new conversation can only be moderated by its creator".into())
+                    }
+                },
+                EntryValidationData::Modify{new_entry, old_entry, validation_data, ..} => {
+                    new_entry.validate_tags()?;
+                    let at = header_time(&validation_data)?;
+                    let sources = validation_data.sources();
+                    if sources.iter().any(|source| old_entry.was_moderated_by(source, at)) {
+                        Ok(())
+                    } else {
+                        Err("Only moderators of the conversation can change it".into())
+                    }
+                },
+                _ => Err("Cannot delete a conversation".into()),
+            }
         },
 
         links: [
@@ -41,20 +294,116 @@ pub fn public_conversation_definition() -> ValidatingEntryType {
                     hdk::ValidationPackageDefinition::Entry
                 },
 
-                validation: |_validation_data: hdk::LinkValidationData| {
-                    Ok(())
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            validate_membership(
+                                link.link.base(),
+                                link.link.target(),
+                                link.link.tag(),
+                                &validation_data,
+                            )
+                        },
+                        // members can leave, moderators can kick them out
+                        hdk::LinkValidationData::LinkRemove { link, validation_data } => {
+                            let sources = validation_data.sources();
+                            if sources.contains(link.link.target()) {
+                                Ok(())
+                            } else {
+                                validate_moderator(link.link.base(), &sources, header_time(&validation_data)?)
+                            }
+                        },
+                    }
                 }
             ),
             to!(
                 "message",
                 link_type: MESSAGE_LINK_TYPE_TO,
 
+                // the author's chain is needed to check slow mode
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::ChainFull
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        // checked against the conversation as it was when the message was linked,
+                        // so a later ban or policy change doesn't invalidate earlier messages
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            let message: Message = get_as_type(link.link.target().clone())
+                                .map_err(|_| String::from("Message could not be found"))?;
+                            let author = Address::from(message.author);
+                            let sources = validation_data.sources();
+                            if !sources.contains(&author) {
+                                return Err("Messages can only be posted to a conversation by their author".into());
+                            }
+                            let posted_at = header_time(&validation_data)?;
+                            let conversation = conversation_at(link.link.base(), posted_at)?;
+                            validate_not_banned(&conversation, &author)?;
+                            validate_posting_policy(
+                                link.link.base(),
+                                &conversation,
+                                link.link.target(),
+                                &sources,
+                                posted_at,
+                                &author_chain(&validation_data),
+                            )
+                        },
+                        // a message can be taken out of a conversation by its author or a moderator
+                        hdk::LinkValidationData::LinkRemove { link, validation_data } => {
+                            let sources = validation_data.sources();
+                            let message: Message = get_as_type(link.link.target().clone())
+                                .map_err(|_| String::from("Message could not be found"))?;
+                            if sources.contains(&Address::from(message.author)) {
+                                Ok(())
+                            } else {
+                                validate_moderator(link.link.base(), &sources, header_time(&validation_data)?)
+                            }
+                        },
+                    }
+                }
+            ),
+            to!(
+                "message",
+                link_type: PINNED_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources, at) = link_sources_and_time(validation_data)?;
+                    validate_moderator(link.base(), &sources, at)
+                }
+            ),
+            from!(
+                "%agent_id",
+                link_type: MEMBER_OF_LINK_TYPE,
+
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
 
-                validation: |_validation_data: hdk::LinkValidationData| {
-                    Ok(())
+                // only the agent themselves can record which conversations they belong to,
+                // moderators can also remove the record when kicking them
+                validation: |validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, validation_data } => {
+                            if validation_data.sources().contains(link.link.base()) {
+                                Ok(())
+                            } else {
+                                Err("Agents can only link themselves to a conversation".into())
+                            }
+                        },
+                        hdk::LinkValidationData::LinkRemove { link, validation_data } => {
+                            let sources = validation_data.sources();
+                            if sources.contains(link.link.base()) {
+                                Ok(())
+                            } else {
+                                validate_moderator(link.link.target(), &sources, header_time(&validation_data)?)
+                            }
+                        },
+                    }
                 }
             )
         ]
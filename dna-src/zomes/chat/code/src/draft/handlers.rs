@@ -0,0 +1,41 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::entry::Entry,
+    holochain_persistence_api::cas::content::Address,
+};
+use crate::draft::Draft;
+use crate::message::MessageSpec;
+use crate::utils::query_local_entries;
+use crate::DRAFT_ENTRY;
+
+fn commit_draft(conversation_address: Address, message: Option<MessageSpec>) -> ZomeApiResult<Address> {
+    let draft = Draft {
+        conversation_address,
+        message,
+    };
+    hdk::commit_entry(&Entry::App(DRAFT_ENTRY.into(), draft.into()))
+}
+
+pub fn handle_save_draft(conversation_address: Address, message: MessageSpec) -> ZomeApiResult<Address> {
+    commit_draft(conversation_address, Some(message))
+}
+
+/// The current draft for a conversation. Its message is empty if there is no draft.
+pub fn handle_get_draft(conversation_address: Address) -> ZomeApiResult<Draft> {
+    Ok(query_local_entries::<Draft>(DRAFT_ENTRY)?
+        .into_iter()
+        .map(|result| result.entry)
+        .filter(|draft| draft.conversation_address == conversation_address)
+        .last()
+        .unwrap_or(Draft {
+            conversation_address,
+            message: None,
+        }))
+}
+
+pub fn handle_discard_draft(conversation_address: Address) -> ZomeApiResult<()> {
+    if handle_get_draft(conversation_address.clone())?.message.is_some() {
+        commit_draft(conversation_address, None)?;
+    }
+    Ok(())
+}
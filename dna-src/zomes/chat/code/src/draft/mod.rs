@@ -0,0 +1,37 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::dna::entry_types::Sharing,
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+use crate::message::MessageSpec;
+
+pub mod handlers;
+
+/// An unsent message for a conversation, kept on the agent's own chain. The latest
+/// draft for a conversation replaces earlier ones and a draft without a message
+/// marks it as discarded.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct Draft {
+    pub conversation_address: Address,
+    pub message: Option<MessageSpec>,
+}
+
+use crate::DRAFT_ENTRY;
+
+pub fn draft_definition() -> ValidatingEntryType {
+    entry!(
+        name: DRAFT_ENTRY,
+        description: "A message that has not been sent yet",
+        sharing: Sharing::Private,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |_validation_data: hdk::EntryValidationData<Draft>| {
+            Ok(())
+        }
+    )
+}
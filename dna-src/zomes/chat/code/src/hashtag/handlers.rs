@@ -0,0 +1,139 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::link::LinkMatch,
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::conversation::Conversation;
+use crate::hashtag::{
+    extract_hashtags, hashtag_anchor, hashtags_anchor, normalize, parse_tagged_message_tag,
+    tagged_message_tag, TimeWindow, TrendingHashtag,
+};
+use crate::message::Message;
+use crate::utils::GetLinksLoadResult;
+use crate::{HASHTAG_LINK_TYPE, MESSAGE_LINK_TYPE_TO, TAGGED_MESSAGE_LINK_TYPE};
+
+/// Link a newly posted message from the anchor of every hashtag in its payload. Only messages
+/// in open conversations are indexed. The message timestamp and conversation are kept in the
+/// link tag so trends can be counted without loading messages.
+pub fn index_message_hashtags(
+    conversation_address: &Address,
+    conversation: &Conversation,
+    message_address: &Address,
+    message: &Message,
+) -> ZomeApiResult<()> {
+    let tags = extract_hashtags(&message.payload);
+    if tags.is_empty() || !conversation.join_policy.is_open() {
+        return Ok(());
+    }
+    let root_address = hdk::commit_entry(&hashtags_anchor())?;
+    for tag in tags {
+        let tag_address = hdk::commit_entry(&hashtag_anchor(&tag))?;
+        hdk::link_entries(&root_address, &tag_address, HASHTAG_LINK_TYPE, tag.as_str())?;
+        hdk::link_entries(
+            &tag_address,
+            message_address,
+            TAGGED_MESSAGE_LINK_TYPE,
+            tagged_message_tag(message.timestamp, conversation_address).as_str(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Take a message that is being removed out of every hashtag it was indexed under
+pub(crate) fn unindex_message_hashtags(message_address: &Address, message: &Message) -> ZomeApiResult<()> {
+    for tag in extract_hashtags(&message.payload) {
+        let tag_address = hdk::entry_address(&hashtag_anchor(&tag))?;
+        for link in hdk::get_links(&tag_address, LinkMatch::Exactly(TAGGED_MESSAGE_LINK_TYPE), LinkMatch::Any)?.links() {
+            if &link.address == message_address {
+                hdk::remove_link(&tag_address, message_address, TAGGED_MESSAGE_LINK_TYPE, link.tag.as_str())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Messages carrying the hashtag that are still posted in an open conversation, newest first.
+/// Messages that have expired as of `now` are left out.
+pub fn handle_get_messages_by_hashtag(
+    tag: String,
+    now: Option<u64>,
+) -> ZomeApiResult<Vec<GetLinksLoadResult<Message>>> {
+    let tag_address = hdk::entry_address(&hashtag_anchor(&normalize(&tag)))?;
+    // the messages still posted in each open conversation, or none if it isn't open
+    let mut posted_in: HashMap<Address, Vec<Address>> = HashMap::new();
+    let mut uniques = HashSet::new();
+    let mut result = Vec::new();
+    for link in hdk::get_links(&tag_address, LinkMatch::Exactly(TAGGED_MESSAGE_LINK_TYPE), LinkMatch::Any)?.links() {
+        let conversation_address = match parse_tagged_message_tag(&link.tag) {
+            Some((_, Some(conversation_address))) => conversation_address,
+            _ => continue,
+        };
+        if !posted_in.contains_key(&conversation_address) {
+            let messages = match get_as_type::<Conversation>(conversation_address.clone()) {
+                Ok(ref conversation) if conversation.join_policy.is_open() => hdk::get_links(
+                    &conversation_address,
+                    LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+                    LinkMatch::Any,
+                )?
+                .addresses(),
+                _ => Vec::new(),
+            };
+            posted_in.insert(conversation_address.clone(), messages);
+        }
+        if !posted_in[&conversation_address].contains(&link.address) || !uniques.insert(link.address.clone()) {
+            continue;
+        }
+        if let Ok(message) = get_as_type::<Message>(link.address.clone()) {
+            if !now.map(|now| message.is_expired(now)).unwrap_or(false) {
+                result.push(GetLinksLoadResult { address: link.address, entry: message });
+            }
+        }
+    }
+    result.sort_by(|a, b| b.entry.timestamp.cmp(&a.entry.timestamp));
+    Ok(result)
+}
+
+/// Hashtags used within the window ordered by how many messages carried them
+pub fn handle_get_trending_hashtags(window: TimeWindow) -> ZomeApiResult<Vec<TrendingHashtag>> {
+    let root_address = hdk::entry_address(&hashtags_anchor())?;
+    let tags: HashSet<String> = hdk::get_links(
+        &root_address,
+        LinkMatch::Exactly(HASHTAG_LINK_TYPE),
+        LinkMatch::Any,
+    )?
+    .links()
+    .into_iter()
+    .map(|link| link.tag)
+    .collect();
+
+    let mut counts = BTreeMap::new();
+    for tag in tags {
+        let tag_address = hdk::entry_address(&hashtag_anchor(&tag))?;
+        let messages_in_window: HashSet<Address> = hdk::get_links(
+            &tag_address,
+            LinkMatch::Exactly(TAGGED_MESSAGE_LINK_TYPE),
+            LinkMatch::Any,
+        )?
+        .links()
+        .into_iter()
+        .filter(|link| {
+            parse_tagged_message_tag(&link.tag)
+                .map(|(timestamp, _)| window.contains(timestamp))
+                .unwrap_or(false)
+        })
+        .map(|link| link.address)
+        .collect();
+        if !messages_in_window.is_empty() {
+            counts.insert(tag, messages_in_window.len());
+        }
+    }
+
+    let mut result: Vec<TrendingHashtag> = counts
+        .into_iter()
+        .map(|(tag, count)| TrendingHashtag { tag, count })
+        .collect();
+    result.sort_by(|a, b| b.count.cmp(&a.count));
+    Ok(result)
+}
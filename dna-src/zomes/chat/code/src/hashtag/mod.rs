@@ -0,0 +1,190 @@
+use hdk::{
+    holochain_core_types::entry::Entry,
+    holochain_json_api::{error::JsonError, json::{JsonString, RawString}},
+    holochain_persistence_api::cas::content::Address,
+};
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+
+use crate::conversation::{conversation_at, validate_moderator};
+use crate::message::Message;
+
+pub mod handlers;
+
+pub const MAX_HASHTAG_LENGTH: usize = 64;
+
+/// A closed range of message timestamps
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimeWindow {
+    pub from: u64,
+    pub to: u64,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, timestamp: u64) -> bool {
+        self.from <= timestamp && timestamp <= self.to
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct TrendingHashtag {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Lowercase a tag, then keep the letters, digits and underscores it starts with.
+/// Lowercasing comes first because it can add characters, such as the combining dot
+/// `İ` turns into, so a stored tag always extracts to itself again.
+fn lowercase_tag(tag: &str) -> String {
+    tag.to_lowercase()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
+}
+
+/// Normalize a user supplied tag so `#Rust` and `rust` refer to the same anchor
+pub fn normalize(tag: &str) -> String {
+    lowercase_tag(tag.trim_start_matches('#'))
+}
+
+/// Hashtags are a `#` at the start of a word followed by letters, digits or underscores
+pub fn extract_hashtags(text: &str) -> BTreeSet<String> {
+    text.split_whitespace()
+        .filter(|word| word.starts_with('#'))
+        .map(|word| lowercase_tag(&word[1..]))
+        .filter(|tag| !tag.is_empty() && tag.chars().count() <= MAX_HASHTAG_LENGTH)
+        .collect()
+}
+
+/// Links from a hashtag to a message are tagged with the message timestamp and the
+/// conversation it was posted to, so trends can be counted without loading messages
+pub fn tagged_message_tag(timestamp: u64, conversation_address: &Address) -> String {
+    format!("{}:{}", timestamp, conversation_address)
+}
+
+/// The timestamp and conversation recorded in a tagged message link. Links made before the
+/// conversation was recorded only carry the timestamp.
+pub fn parse_tagged_message_tag(tag: &str) -> Option<(u64, Option<Address>)> {
+    let mut parts = tag.splitn(2, ':');
+    let timestamp = parts.next()?.parse().ok()?;
+    Some((timestamp, parts.next().map(Address::from)))
+}
+
+/// The anchor all messages carrying a given hashtag are linked from
+pub fn hashtag_anchor(tag: &str) -> Entry {
+    Entry::App(
+        "anchor".into(),
+        RawString::from(format!("hashtag:{}", tag)).into(),
+    )
+}
+
+/// The anchor every hashtag anchor is linked from
+pub fn hashtags_anchor() -> Entry {
+    Entry::App("anchor".into(), RawString::from("hashtags").into())
+}
+
+/// A hashtag anchor can only be linked from the root anchor under its own tag
+pub fn validate_hashtag_link(base: &Address, target: &Address, tag: &str) -> Result<(), String> {
+    let root_address = hdk::entry_address(&hashtags_anchor()).map_err(|e| e.to_string())?;
+    let tag_address = hdk::entry_address(&hashtag_anchor(tag)).map_err(|e| e.to_string())?;
+    if base != &root_address || target != &tag_address {
+        return Err("Hashtag link does not match its tag".into());
+    }
+    if extract_hashtags(&format!("#{}", tag)).contains(tag) {
+        Ok(())
+    } else {
+        Err("Invalid hashtag".into())
+    }
+}
+
+fn load_message(address: &Address) -> Result<Message, String> {
+    match hdk::get_entry(address) {
+        Ok(Some(Entry::App(_, entry_value))) => Message::try_from(entry_value)
+            .map_err(|_| String::from("Tagged message link target is not a message")),
+        _ => Err("Tagged message link target is not a message".into()),
+    }
+}
+
+/// A message can only be linked by its author from the anchor of a hashtag its payload
+/// carries, tagged with the message timestamp and the open conversation it was posted to
+pub fn validate_tagged_message_link(
+    base: &Address,
+    target: &Address,
+    tag: &str,
+    sources: &[Address],
+    at: u64,
+) -> Result<(), String> {
+    let message = load_message(target)?;
+    if !sources.contains(&Address::from(message.author.clone())) {
+        return Err("Only the author can index a message by its hashtags".into());
+    }
+    let conversation_address = match parse_tagged_message_tag(tag) {
+        Some((timestamp, Some(conversation_address))) if timestamp == message.timestamp => conversation_address,
+        _ => return Err("Tagged message link tag must be the message timestamp and conversation".into()),
+    };
+    if !conversation_at(&conversation_address, at)?.join_policy.is_open() {
+        return Err("Only messages in open conversations are indexed by hashtag".into());
+    }
+    for hashtag in extract_hashtags(&message.payload) {
+        if base == &hdk::entry_address(&hashtag_anchor(&hashtag)).map_err(|e| e.to_string())? {
+            return Ok(());
+        }
+    }
+    Err("Message does not carry this hashtag".into())
+}
+
+/// A message can be taken out of a hashtag by its author or a moderator of its conversation
+pub fn validate_tagged_message_unlink(target: &Address, tag: &str, sources: &[Address], at: u64) -> Result<(), String> {
+    let message = load_message(target)?;
+    if sources.contains(&Address::from(message.author)) {
+        return Ok(());
+    }
+    match parse_tagged_message_tag(tag) {
+        Some((_, Some(conversation_address))) => validate_moderator(&conversation_address, sources, at),
+        _ => Err("Only the author can take this message out of a hashtag".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_lowercased_and_stop_at_punctuation() {
+        let tags = extract_hashtags("Shipping #Release, notes in #release_notes and #QA!");
+        assert_eq!(tags.into_iter().collect::<Vec<_>>(), vec!["qa", "release", "release_notes"]);
+        assert!(extract_hashtags("no # tags# here").is_empty());
+    }
+
+    #[test]
+    fn non_ascii_tags_extract_to_themselves() {
+        for text in &["#İstanbul", "#Ünïcode", "#ΣΊΣΥΦΟΣ", "#日本語"] {
+            let tags = extract_hashtags(text);
+            assert_eq!(tags.len(), 1, "{}", text);
+            for tag in tags {
+                assert_eq!(normalize(text), tag);
+                assert!(extract_hashtags(&format!("#{}", tag)).contains(&tag), "{}", tag);
+            }
+        }
+    }
+
+    #[test]
+    fn tags_are_limited_after_lowercasing() {
+        // each `İ` lowercases to two characters
+        let long = format!("#{}", "İ".repeat(MAX_HASHTAG_LENGTH));
+        for tag in extract_hashtags(&long) {
+            assert!(tag.chars().count() <= MAX_HASHTAG_LENGTH);
+        }
+        let too_long = format!("#{}", "a".repeat(MAX_HASHTAG_LENGTH + 1));
+        assert!(extract_hashtags(&too_long).is_empty());
+    }
+
+    #[test]
+    fn tagged_message_tags_round_trip() {
+        let conversation_address = Address::from("QmConversation");
+        let tag = tagged_message_tag(42, &conversation_address);
+        assert_eq!(parse_tagged_message_tag(&tag), Some((42, Some(conversation_address))));
+        assert_eq!(parse_tagged_message_tag("42"), Some((42, None)));
+        assert_eq!(parse_tagged_message_tag("soon"), None);
+    }
+}
@@ -0,0 +1,98 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::entry::Entry,
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+    AGENT_ADDRESS,
+};
+use crate::conversation::handlers::{join_with_proof, require_moderator};
+use crate::conversation::MembershipProof;
+use crate::invite::{
+    from_token, invite_uses, secret_hash, to_token, validate_invite_use, Invite, InviteStatus,
+};
+use crate::utils::query_local_entries;
+use crate::INVITE_ENTRY;
+
+/// The secret part of an invite's token, which only its creator can produce
+fn invite_secret(conversation_address: &Address, max_uses: u32, expires_at: u64, nonce: u64) -> ZomeApiResult<String> {
+    hdk::sign(format!("invite:{}:{}:{}:{}", conversation_address, max_uses, expires_at, nonce))
+}
+
+/// Create an invite to a conversation this agent moderates. Returns the token to share.
+pub fn handle_create_invite(
+    conversation_address: Address,
+    max_uses: u32,
+    expires_at: u64,
+) -> ZomeApiResult<String> {
+    require_moderator(&conversation_address)?;
+    let nonce = query_local_entries::<Invite>(INVITE_ENTRY)?.len() as u64;
+    let secret = invite_secret(&conversation_address, max_uses, expires_at, nonce)?;
+    let invite = Invite {
+        conversation_address,
+        creator: AGENT_ADDRESS.to_string().into(),
+        max_uses,
+        expires_at,
+        nonce,
+        secret_hash: secret_hash(&secret)?,
+    };
+    let invite_address = hdk::commit_entry(&Entry::App(INVITE_ENTRY.into(), invite.into()))?;
+    Ok(to_token(&invite_address, &secret))
+}
+
+/// Load the invite a token is for, checking the token carries its secret
+fn invite_for_token(token: &str) -> ZomeApiResult<(Address, String, Invite)> {
+    let invalid = || ZomeApiError::Internal("Invite could not be found or has been revoked".into());
+    let (invite_address, secret) = from_token(token).ok_or_else(invalid)?;
+    let invite: Invite = get_as_type(invite_address.clone()).map_err(|_| invalid())?;
+    if invite.secret_hash != secret_hash(&secret)? {
+        return Err(invalid());
+    }
+    Ok((invite_address, secret, invite))
+}
+
+/// Join the conversation an invite is for. `now` is only used to fail early on an expired
+/// invite, validation checks the expiry against the time the membership link was committed.
+pub fn handle_redeem_invite(token: String, now: u64) -> ZomeApiResult<()> {
+    let (invite_address, secret, invite) = invite_for_token(&token)?;
+    validate_invite_use(&invite.conversation_address, &AGENT_ADDRESS, &invite_address, &secret, now)
+        .map_err(ZomeApiError::Internal)?;
+    join_with_proof(
+        invite.conversation_address,
+        MembershipProof::Invite { invite: invite_address, secret },
+    )
+}
+
+/// Invites this agent created that have not been revoked, expired or used up as of `now`
+pub fn handle_list_invites(now: u64) -> ZomeApiResult<Vec<InviteStatus>> {
+    let mut outstanding = Vec::new();
+    for result in query_local_entries::<Invite>(INVITE_ENTRY)? {
+        // revoked invites can no longer be loaded from the DHT
+        if hdk::get_entry(&result.address)?.is_none() || result.entry.expires_at <= now {
+            continue;
+        }
+        let uses = invite_uses(&result.entry.conversation_address, &result.address)
+            .map_err(ZomeApiError::Internal)?
+            .len() as u32;
+        if uses < result.entry.max_uses {
+            let invite = result.entry;
+            let secret = invite_secret(&invite.conversation_address, invite.max_uses, invite.expires_at, invite.nonce)?;
+            outstanding.push(InviteStatus {
+                token: to_token(&result.address, &secret),
+                address: result.address,
+                invite,
+                uses,
+            });
+        }
+    }
+    Ok(outstanding)
+}
+
+/// Stop an invite from being redeemed. Agents who already joined with it stay members.
+pub fn handle_revoke_invite(token: String) -> ZomeApiResult<()> {
+    let (invite_address, _, invite) = invite_for_token(&token)?;
+    if invite.creator != Address::from(AGENT_ADDRESS.to_string()) {
+        return Err(ZomeApiError::Internal("Only the creator can revoke an invite".into()));
+    }
+    hdk::remove_entry(&invite_address)?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    error::ZomeApiResult,
+    holochain_core_types::{
+        dna::entry_types::Sharing, entry::Entry, link::LinkMatch, validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::{JsonString, RawString}},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+use std::collections::HashSet;
+
+use crate::conversation::{validate_moderator, MembershipProof};
+use crate::utils::header_time;
+
+pub mod handlers;
+
+/// An invite to a conversation that only lets in agents who have one. The token that is
+/// shared is the address of the entry followed by a secret, the creator's signature of the
+/// invite. Only a hash of the secret is stored, so the invite address alone can't be used to
+/// redeem it. Redeeming records the secret in the agent's `has_member` link for validators to
+/// check, so from then on anyone reading the members can use it too, like anyone the token is
+/// passed on to; `max_uses` and `expires_at` bound how far it goes.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct Invite {
+    pub conversation_address: Address,
+    pub creator: Address,
+    pub max_uses: u32,
+    pub expires_at: u64,
+    // counts the creator's invites so they can sign each one differently
+    pub nonce: u64,
+    pub secret_hash: Address,
+}
+
+/// An outstanding invite as listed for its creator
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct InviteStatus {
+    pub token: String,
+    pub address: Address,
+    pub invite: Invite,
+    pub uses: u32,
+}
+
+/// The hash of an invite secret stored in the invite
+pub fn secret_hash(secret: &str) -> ZomeApiResult<Address> {
+    hdk::entry_address(&Entry::App("anchor".into(), RawString::from(format!("invite:{}", secret)).into()))
+}
+
+/// Join the invite address and secret into the token that is shared
+pub fn to_token(invite_address: &Address, secret: &str) -> String {
+    format!("{}:{}", invite_address, secret)
+}
+
+/// Split a token into the invite address and secret
+pub fn from_token(token: &str) -> Option<(Address, String)> {
+    let mut parts = token.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(address), Some(secret)) if !address.is_empty() && !secret.is_empty() => {
+            Some((address.into(), secret.to_owned()))
+        }
+        _ => None,
+    }
+}
+
+/// The agents that have joined a conversation using the invite
+pub fn invite_uses(conversation_address: &Address, invite_address: &Address) -> Result<HashSet<Address>, String> {
+    Ok(hdk::get_links(conversation_address, LinkMatch::Exactly(PUBLIC_STREAM_LINK_TYPE_TO), LinkMatch::Any)
+        .map_err(|_| String::from("Could not load the conversation's members"))?
+        .links()
+        .into_iter()
+        .filter(|link| match MembershipProof::from_tag(&link.tag) {
+            Some(MembershipProof::Invite { invite, .. }) => &invite == invite_address,
+            _ => false,
+        })
+        .map(|link| link.address)
+        .collect())
+}
+
+/// Checks that an agent joined the conversation with the secret of an invite that was still good
+/// when they joined. Validation passes the time from the header of their `has_member` link, which
+/// the joining agent writes themselves, so an agent holding the secret can get past the expiry by
+/// backdating it; the use count and revoking the invite still hold.
+pub fn validate_invite_use(
+    conversation_address: &Address,
+    agent_address: &Address,
+    invite_address: &Address,
+    secret: &str,
+    joined_at: u64,
+) -> Result<(), String> {
+    let invite: Invite = get_as_type(invite_address.clone())
+        .map_err(|_| String::from("Invite could not be found or has been revoked"))?;
+    if secret_hash(secret).map_err(|_| String::from("Invite secret could not be hashed"))? != invite.secret_hash {
+        return Err("Invite secret does not match".into());
+    }
+    if &invite.conversation_address != conversation_address {
+        return Err("Invite is for a different conversation".into());
+    }
+    if joined_at >= invite.expires_at {
+        return Err("Invite has expired".into());
+    }
+    let mut uses = invite_uses(conversation_address, invite_address)?;
+    uses.remove(agent_address);
+    if uses.len() >= invite.max_uses as usize {
+        return Err("Invite has been used up".into());
+    }
+    Ok(())
+}
+
+use crate::{INVITE_ENTRY, PUBLIC_STREAM_LINK_TYPE_TO};
+
+pub fn invite_definition() -> ValidatingEntryType {
+    entry!(
+        name: INVITE_ENTRY,
+        description: "An invite to join a conversation",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<Invite>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    if !validation_data.sources().contains(&entry.creator) {
+                        return Err("Invites can only be created as oneself".into());
+                    }
+                    if entry.max_uses == 0 {
+                        return Err("An invite must allow at least one use".into());
+                    }
+                    validate_moderator(
+                        &entry.conversation_address,
+                        &[entry.creator.clone()],
+                        header_time(&validation_data)?,
+                    )
+                },
+                // revoking an invite deletes it
+                EntryValidationData::Delete{old_entry, validation_data, ..} => {
+                    if validation_data.sources().contains(&old_entry.creator) {
+                        Ok(())
+                    } else {
+                        Err("Only the creator can revoke an invite".into())
+                    }
+                },
+                _ => Err("Cannot change an invite".into()),
+            }
+        }
+    )
+}
@@ -0,0 +1,169 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+    AGENT_ADDRESS,
+};
+use std::collections::HashSet;
+use crate::audit::{handlers::record_moderation, ModerationAction};
+use crate::community::Community;
+use crate::conversation::handlers::{join_with_proof, notify_agent, require_moderator};
+use crate::conversation::{Conversation, JoinPolicy, MembershipProof};
+use crate::join_request::{JoinDecision, JoinRequest, JoinRequestState, JoinRequestStatus};
+use crate::utils::{get_links_and_load_type, query_local_entries, GetLinksLoadResult};
+use crate::{
+    DirectMessage, JoinRequestSignalPayload, JOIN_DECISION_ENTRY, JOIN_DECISION_LINK_TYPE,
+    JOIN_REQUEST_ENTRY, JOIN_REQUEST_LINK_TYPE,
+};
+
+/// The decision that settles a request. If moderators disagreed an approval wins.
+fn get_decision(request_address: &Address) -> ZomeApiResult<Option<GetLinksLoadResult<JoinDecision>>> {
+    let mut decisions = get_links_and_load_type::<JoinDecision>(
+        request_address,
+        LinkMatch::Exactly(JOIN_DECISION_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    decisions.sort_by_key(|decision| !decision.entry.approved);
+    Ok(decisions.into_iter().next())
+}
+
+fn status_of(decision: &Option<GetLinksLoadResult<JoinDecision>>) -> JoinRequestStatus {
+    match decision {
+        None => JoinRequestStatus::Pending,
+        Some(decision) if decision.entry.approved => JoinRequestStatus::Approved,
+        Some(_) => JoinRequestStatus::Denied,
+    }
+}
+
+/// Tell the requester, every moderator and the admins of the conversation's community
+/// where a request stands
+fn notify_join_request(
+    conversation: &Conversation,
+    request_address: Address,
+    request: &JoinRequest,
+    status: JoinRequestStatus,
+) {
+    let notification = DirectMessage::JoinRequestNotification(JoinRequestSignalPayload {
+        conversation_address: request.conversation_address.clone(),
+        request_address,
+        requester: request.requester.clone(),
+        status,
+    });
+    let admins = conversation.community
+        .as_ref()
+        .and_then(|community_address| get_as_type::<Community>(community_address.clone()).ok())
+        .map(|community| community.admins)
+        .unwrap_or_default();
+    let mut uniques = HashSet::new();
+    conversation.moderators
+        .iter()
+        .chain(admins.iter())
+        .chain(std::iter::once(&request.requester))
+        .filter(|agent_id| uniques.insert(agent_id.to_string()))
+        .for_each(|agent_id| notify_agent(agent_id, &notification));
+}
+
+/// Ask the moderators of a conversation that needs approval to let this agent in.
+/// Asking again returns the same request unless it was denied, in which case a new one is made.
+pub fn handle_request_to_join(conversation_address: Address) -> ZomeApiResult<Address> {
+    let conversation: Conversation = get_as_type(conversation_address.clone())?;
+    if conversation.join_policy != JoinPolicy::Approval {
+        return Err(ZomeApiError::Internal(
+            "This conversation does not take join requests".into(),
+        ));
+    }
+    let earlier: Vec<Address> = query_local_entries::<JoinRequest>(JOIN_REQUEST_ENTRY)?
+        .into_iter()
+        .filter(|result| result.entry.conversation_address == conversation_address)
+        .map(|result| result.address)
+        .collect();
+    if let Some(latest) = earlier.last() {
+        if status_of(&get_decision(latest)?) != JoinRequestStatus::Denied {
+            return Ok(latest.clone());
+        }
+    }
+    let request = JoinRequest {
+        conversation_address: conversation_address.clone(),
+        requester: AGENT_ADDRESS.to_string().into(),
+        nonce: earlier.len() as u64,
+    };
+    let request_address =
+        hdk::commit_entry(&Entry::App(JOIN_REQUEST_ENTRY.into(), request.clone().into()))?;
+    hdk::link_entries(&conversation_address, &request_address, JOIN_REQUEST_LINK_TYPE, "")?;
+    notify_join_request(&conversation, request_address.clone(), &request, JoinRequestStatus::Pending);
+    Ok(request_address)
+}
+
+/// Join a conversation that needs approval once a moderator has approved this agent,
+/// asking to join if they haven't yet or were denied last time
+pub(crate) fn join_when_approved(conversation_address: Address) -> ZomeApiResult<()> {
+    let request_address = handle_request_to_join(conversation_address.clone())?;
+    match get_decision(&request_address)? {
+        None => Ok(()),
+        Some(decision) if decision.entry.approved => join_with_proof(
+            conversation_address,
+            MembershipProof::Approved { decision: decision.address },
+        ),
+        // the denied request was just replaced with a new one
+        Some(_) => Ok(()),
+    }
+}
+
+/// Every join request made to a conversation this agent moderates
+pub fn handle_get_join_requests(conversation_address: Address) -> ZomeApiResult<Vec<JoinRequestState>> {
+    require_moderator(&conversation_address)?;
+    let mut requests = get_links_and_load_type::<JoinRequest>(
+        &conversation_address,
+        LinkMatch::Exactly(JOIN_REQUEST_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    requests.retain(|e| uniques.insert(e.address.clone()));
+    requests
+        .into_iter()
+        .map(|result| {
+            Ok(JoinRequestState {
+                status: status_of(&get_decision(&result.address)?),
+                address: result.address,
+                request: result.entry,
+            })
+        })
+        .collect()
+}
+
+fn decide(request_address: Address, approved: bool) -> ZomeApiResult<()> {
+    let request: JoinRequest = get_as_type(request_address.clone())?;
+    require_moderator(&request.conversation_address)?;
+    if get_decision(&request_address)?.is_some() {
+        return Err(ZomeApiError::Internal("Join request has already been decided".into()));
+    }
+    let decision = JoinDecision {
+        request: request_address.clone(),
+        approved,
+        moderator: AGENT_ADDRESS.to_string().into(),
+    };
+    let decision_address =
+        hdk::commit_entry(&Entry::App(JOIN_DECISION_ENTRY.into(), decision.into()))?;
+    hdk::link_entries(&request_address, &decision_address, JOIN_DECISION_LINK_TYPE, "")?;
+    let (action, status) = if approved {
+        (ModerationAction::ApproveJoin(request.requester.clone()), JoinRequestStatus::Approved)
+    } else {
+        (ModerationAction::DenyJoin(request.requester.clone()), JoinRequestStatus::Denied)
+    };
+    record_moderation(&request.conversation_address, action)?;
+    let conversation: Conversation = get_as_type(request.conversation_address.clone())?;
+    notify_join_request(&conversation, request_address, &request, status);
+    Ok(())
+}
+
+/// Let the requester in. Approving does not make them a member, only the requester can add
+/// themselves to the conversation. They are told of the approval with a `join_request` signal
+/// and become a member the next time they call `join_conversation`.
+pub fn handle_approve_join(request_address: Address) -> ZomeApiResult<()> {
+    decide(request_address, true)
+}
+
+pub fn handle_deny_join(request_address: Address) -> ZomeApiResult<()> {
+    decide(request_address, false)
+}
@@ -0,0 +1,169 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::{dna::entry_types::Sharing, validation::EntryValidationData},
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+
+use crate::conversation::validate_moderator;
+use crate::utils::{header_time, link_and_sources};
+
+pub mod handlers;
+
+/// An agent asking to be let into a conversation that needs approval
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct JoinRequest {
+    pub conversation_address: Address,
+    pub requester: Address,
+    // counts the requester's earlier requests to the conversation so they can ask again after a denial
+    pub nonce: u64,
+}
+
+/// A moderator's answer to a join request
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct JoinDecision {
+    pub request: Address,
+    pub approved: bool,
+    pub moderator: Address,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinRequestStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+/// A join request along with where it stands
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct JoinRequestState {
+    pub address: Address,
+    pub request: JoinRequest,
+    pub status: JoinRequestStatus,
+}
+
+/// Checks that an agent joining a conversation had their request approved
+pub fn validate_approval(
+    conversation_address: &Address,
+    agent_address: &Address,
+    decision_address: &Address,
+) -> Result<(), String> {
+    let decision: JoinDecision = get_as_type(decision_address.clone())
+        .map_err(|_| String::from("Join decision could not be found"))?;
+    let request: JoinRequest = get_as_type(decision.request.clone())
+        .map_err(|_| String::from("Join request could not be found"))?;
+    if &request.conversation_address != conversation_address || &request.requester != agent_address {
+        return Err("Join decision is for a different request".into());
+    }
+    if decision.approved {
+        Ok(())
+    } else {
+        Err("Join request was denied".into())
+    }
+}
+
+use crate::{
+    JOIN_DECISION_ENTRY, JOIN_DECISION_LINK_TYPE, JOIN_REQUEST_ENTRY, JOIN_REQUEST_LINK_TYPE,
+};
+
+pub fn join_request_definition() -> ValidatingEntryType {
+    entry!(
+        name: JOIN_REQUEST_ENTRY,
+        description: "A request to join a conversation that needs approval",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<JoinRequest>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    if validation_data.sources().contains(&entry.requester) {
+                        Ok(())
+                    } else {
+                        Err("Agents can only ask to join for themselves".into())
+                    }
+                },
+                _ => Err("Cannot change or delete a join request".into()),
+            }
+        },
+
+        links: [
+            from!(
+                "public_conversation",
+                link_type: JOIN_REQUEST_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources) = link_and_sources(validation_data);
+                    let request: JoinRequest = get_as_type(link.target().clone())
+                        .map_err(|_| String::from("Join request could not be found"))?;
+                    if &request.conversation_address == link.base() && sources.contains(&request.requester) {
+                        Ok(())
+                    } else {
+                        Err("Join requests can only be linked from their conversation by the requester".into())
+                    }
+                }
+            )
+        ]
+    )
+}
+
+pub fn join_decision_definition() -> ValidatingEntryType {
+    entry!(
+        name: JOIN_DECISION_ENTRY,
+        description: "A moderator approving or denying a join request",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<JoinDecision>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    if !validation_data.sources().contains(&entry.moderator) {
+                        return Err("Join requests can only be decided as oneself".into());
+                    }
+                    let request: JoinRequest = get_as_type(entry.request.clone())
+                        .map_err(|_| String::from("Join request could not be found"))?;
+                    validate_moderator(
+                        &request.conversation_address,
+                        &[entry.moderator.clone()],
+                        header_time(&validation_data)?,
+                    )
+                },
+                _ => Err("Cannot change or delete a join decision".into()),
+            }
+        },
+
+        links: [
+            from!(
+                "join_request",
+                link_type: JOIN_DECISION_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources) = link_and_sources(validation_data);
+                    let decision: JoinDecision = get_as_type(link.target().clone())
+                        .map_err(|_| String::from("Join decision could not be found"))?;
+                    if &decision.request == link.base() && sources.contains(&decision.moderator) {
+                        Ok(())
+                    } else {
+                        Err("Join decisions can only be linked from their request by the moderator".into())
+                    }
+                }
+            )
+        ]
+    )
+}
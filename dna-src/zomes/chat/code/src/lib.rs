@@ -22,19 +22,73 @@ use std::convert::TryInto;
 use hdk_proc_macros::zome;
 use utils::GetLinksLoadResult;
 
+pub mod activity;
 pub mod anchor;
+pub mod attachment;
+pub mod audit;
+pub mod block;
+pub mod category;
+pub mod community;
 pub mod conversation;
+pub mod draft;
+pub mod hashtag;
+pub mod invite;
+pub mod join_request;
 pub mod member;
 pub mod message;
+pub mod notification;
+pub mod poll;
+pub mod report;
+pub mod schedule;
+pub mod search;
 mod utils;
 
 pub static MESSAGE_ENTRY: &str = "message";
 pub static MESSAGE_LINK_TYPE_TO: &str = "message_in";
 pub static PUBLIC_STREAM_ENTRY: &str = "public_conversation";
 pub static PUBLIC_STREAM_LINK_TYPE_TO: &str = "has_member";
+pub static MEMBER_OF_LINK_TYPE: &str = "member_of";
+pub static MENTIONS_LINK_TYPE: &str = "mentions";
+pub static SEARCH_INDEX_ENTRY: &str = "search_index";
+pub static HASHTAG_LINK_TYPE: &str = "hashtag";
+pub static TAGGED_MESSAGE_LINK_TYPE: &str = "tagged_message";
+pub static PINNED_LINK_TYPE: &str = "pinned";
+pub static ATTACHMENT_ENTRY: &str = "attachment";
+pub static ATTACHMENT_CHUNK_ENTRY: &str = "attachment_chunk";
+pub static AVATAR_ENTRY: &str = "avatar";
+pub static VOTE_ENTRY: &str = "vote";
+pub static VOTE_LINK_TYPE: &str = "vote";
+pub static SCHEDULED_MESSAGE_ENTRY: &str = "scheduled_message";
+pub static SCHEDULED_MESSAGE_DONE_ENTRY: &str = "scheduled_message_done";
+pub static DRAFT_ENTRY: &str = "draft";
+pub static NOTIFICATION_SETTING_ENTRY: &str = "notification_setting";
+pub static BLOCK_LIST_ENTRY: &str = "block_list";
+pub static REPORT_ENTRY: &str = "report";
+pub static REPORT_LINK_TYPE: &str = "report";
+pub static REPORT_RESOLUTION_ENTRY: &str = "report_resolution";
+pub static RESOLUTION_LINK_TYPE: &str = "resolution";
+pub static AUDIT_ENTRY: &str = "audit_entry";
+pub static AUDIT_LINK_TYPE: &str = "audit";
+pub static INVITE_ENTRY: &str = "invite";
+pub static JOIN_REQUEST_ENTRY: &str = "join_request";
+pub static JOIN_REQUEST_LINK_TYPE: &str = "join_request";
+pub static JOIN_DECISION_ENTRY: &str = "join_decision";
+pub static JOIN_DECISION_LINK_TYPE: &str = "decision";
+pub static CATEGORY_ENTRY: &str = "category";
+pub static CATEGORY_LINK_TYPE: &str = "category";
+pub static IN_CATEGORY_LINK_TYPE: &str = "in_category";
+pub static COMMUNITY_ENTRY: &str = "community";
+pub static COMMUNITY_LINK_TYPE: &str = "community";
+pub static COMMUNITY_MEMBER_LINK_TYPE: &str = "community_member";
+pub static MEMBER_OF_COMMUNITY_LINK_TYPE: &str = "member_of_community";
+pub static ACTIVITY_ENTRY: &str = "conversation_activity";
+pub static ACTIVITY_LINK_TYPE: &str = "activity";
 
 pub const CHANNEL_MESSAGE_SIGNAL_TYPE: &str = "new_convo_message";
 pub const JOIN_CHANNEL_SIGNAL_TYPE: &str = "join_convo_message";
+pub const MENTION_SIGNAL_TYPE: &str = "new_mention";
+pub const POLL_TALLY_SIGNAL_TYPE: &str = "poll_tally";
+pub const JOIN_REQUEST_SIGNAL_TYPE: &str = "join_request";
 
 
 #[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
@@ -52,11 +106,31 @@ struct JoinChannelSignalPayload {
     agent_address: Address,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PollTallySignalPayload {
+    conversation_address: Address,
+    message_address: Address,
+    results: poll::PollResults,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct JoinRequestSignalPayload {
+    conversation_address: Address,
+    request_address: Address,
+    requester: Address,
+    status: join_request::JoinRequestStatus,
+}
+
 /// Fully typed definition of the types of direct messages
 #[derive(Clone, Serialize, Deserialize, Debug, DefaultJson, PartialEq)]
 enum DirectMessage {
 	ChannelMessageNotification(NotificationSignalPayload),
-	JoinChannelNotification(JoinChannelSignalPayload)
+	JoinChannelNotification(JoinChannelSignalPayload),
+	MentionNotification(NotificationSignalPayload),
+	PollTallyNotification(PollTallySignalPayload),
+	JoinRequestNotification(JoinRequestSignalPayload),
 }
 
 
@@ -67,6 +141,9 @@ struct NamePayload {
 }
 
 pub (crate) fn signal_ui(message: &DirectMessage) {
+    if !notification::handlers::should_signal(message) {
+        return;
+    }
     match message {
         DirectMessage::ChannelMessageNotification(signal_payload) => {
             // send a signal to the UI which it can use to reactively display messages
@@ -81,6 +158,27 @@ pub (crate) fn signal_ui(message: &DirectMessage) {
                 JOIN_CHANNEL_SIGNAL_TYPE,
                 signal_payload,
             ).ok();
+        },
+        DirectMessage::MentionNotification(signal_payload) => {
+            // signal the UI that this agent was mentioned in a message
+            hdk::emit_signal(
+                MENTION_SIGNAL_TYPE,
+                signal_payload,
+            ).ok();
+        },
+        DirectMessage::PollTallyNotification(signal_payload) => {
+            // signal the UI with the latest results of a poll
+            hdk::emit_signal(
+                POLL_TALLY_SIGNAL_TYPE,
+                signal_payload,
+            ).ok();
+        },
+        DirectMessage::JoinRequestNotification(signal_payload) => {
+            // signal the UI that a join request was made, approved or denied
+            hdk::emit_signal(
+                JOIN_REQUEST_SIGNAL_TYPE,
+                signal_payload,
+            ).ok();
         }
     };
 }
@@ -101,6 +199,10 @@ pub mod chat {
     #[receive]
     pub fn receive(from: Address, msg_json: JsonString) -> String {
         hdk::debug(format!("New direct message from: {:?}", from)).ok();
+        // quietly drop anything from a blocked agent so they can't tell they were blocked
+        if block::handlers::is_blocked(&from).unwrap_or(false) {
+            return String::from("Ok");
+        }
         let maybe_message: Result<DirectMessage, _> = JsonString::from_json(&msg_json).try_into();
         match maybe_message {
             Err(err) => format!("Err({})", err),
@@ -126,22 +228,132 @@ pub mod chat {
         member::profile_definition()
     }
 
+    #[entry_def]
+    pub fn avatar_entry_def() -> ValidatingEntryType {
+        member::avatar::avatar_definition()
+    }
+
+    #[entry_def]
+    pub fn vote_entry_def() -> ValidatingEntryType {
+        poll::vote_definition()
+    }
+
+    #[entry_def]
+    pub fn scheduled_message_entry_def() -> ValidatingEntryType {
+        schedule::scheduled_message_definition()
+    }
+
+    #[entry_def]
+    pub fn scheduled_message_done_entry_def() -> ValidatingEntryType {
+        schedule::scheduled_message_done_definition()
+    }
+
+    #[entry_def]
+    pub fn draft_entry_def() -> ValidatingEntryType {
+        draft::draft_definition()
+    }
+
+    #[entry_def]
+    pub fn notification_setting_entry_def() -> ValidatingEntryType {
+        notification::notification_setting_definition()
+    }
+
+    #[entry_def]
+    pub fn block_list_entry_def() -> ValidatingEntryType {
+        block::block_list_definition()
+    }
+
+    #[entry_def]
+    pub fn report_entry_def() -> ValidatingEntryType {
+        report::report_definition()
+    }
+
+    #[entry_def]
+    pub fn report_resolution_entry_def() -> ValidatingEntryType {
+        report::report_resolution_definition()
+    }
+
+    #[entry_def]
+    pub fn audit_entry_def() -> ValidatingEntryType {
+        audit::audit_entry_definition()
+    }
+
+    #[entry_def]
+    pub fn invite_entry_def() -> ValidatingEntryType {
+        invite::invite_definition()
+    }
+
+    #[entry_def]
+    pub fn join_request_entry_def() -> ValidatingEntryType {
+        join_request::join_request_definition()
+    }
+
+    #[entry_def]
+    pub fn join_decision_entry_def() -> ValidatingEntryType {
+        join_request::join_decision_definition()
+    }
+
+    #[entry_def]
+    pub fn category_entry_def() -> ValidatingEntryType {
+        category::category_definition()
+    }
+
+    #[entry_def]
+    pub fn community_entry_def() -> ValidatingEntryType {
+        community::community_definition()
+    }
+
+    #[entry_def]
+    pub fn activity_entry_def() -> ValidatingEntryType {
+        activity::activity_definition()
+    }
+
     #[entry_def]
     pub fn anchor_entry_def() -> ValidatingEntryType {
         anchor::anchor_definition()
     }
 
+    #[entry_def]
+    pub fn search_index_entry_def() -> ValidatingEntryType {
+        search::search_index_definition()
+    }
+
+    #[entry_def]
+    pub fn attachment_entry_def() -> ValidatingEntryType {
+        attachment::attachment_definition()
+    }
+
+    #[entry_def]
+    pub fn attachment_chunk_entry_def() -> ValidatingEntryType {
+        attachment::attachment_chunk_definition()
+    }
+
     #[zome_fn("hc_public")]
-    pub fn register(name: String, avatar_url: String) -> ZomeApiResult<Address> {
-        member::handlers::handle_register(name, avatar_url)
+    pub fn register(
+        name: String,
+        avatar_url: String,
+        avatar: Option<Address>,
+    ) -> ZomeApiResult<Address> {
+        member::handlers::handle_register(name, avatar_url, avatar)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn upload_avatar(data: Vec<u8>) -> ZomeApiResult<Address> {
+        member::handlers::handle_upload_avatar(data)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_avatar(agent_address: Address) -> ZomeApiResult<member::avatar::AvatarSource> {
+        member::handlers::handle_get_avatar(agent_address)
     }
 
     #[zome_fn("hc_public")]
     pub fn start_conversation(
         name: String,
         description: String,
+        community: Option<Address>,
     ) -> ZomeApiResult<Address> {
-        conversation::handlers::handle_start_conversation(name, description)
+        conversation::handlers::handle_start_conversation(name, description, community)
     }
 
     #[zome_fn("hc_public")]
@@ -151,13 +363,32 @@ pub mod chat {
 
     #[zome_fn("hc_public")]
     pub fn get_all_public_conversations(
+        community: Option<Address>,
     ) -> ZomeApiResult<Vec<GetLinksLoadResult<conversation::Conversation>>> {
-        conversation::handlers::handle_get_all_public_conversations()
+        conversation::handlers::handle_get_all_public_conversations(community)
     }
 
     #[zome_fn("hc_public")]
-    pub fn get_members(conversation_address: Address) -> ZomeApiResult<Vec<Address>> {
-        conversation::handlers::handle_get_members(conversation_address)
+    pub fn get_members(
+        conversation_address: Address,
+        community: Option<Address>,
+    ) -> ZomeApiResult<Vec<Address>> {
+        conversation::handlers::handle_get_members(conversation_address, community)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_my_conversations(
+        community: Option<Address>,
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<conversation::Conversation>>> {
+        conversation::handlers::handle_get_my_conversations(community)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_member_conversations(
+        agent_address: Address,
+        community: Option<Address>,
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<conversation::Conversation>>> {
+        conversation::handlers::handle_get_member_conversations(agent_address, community)
     }
 
     #[zome_fn("hc_public")]
@@ -175,13 +406,433 @@ pub mod chat {
         conversation_address: Address,
         message: message::MessageSpec,
     ) -> ZomeApiResult<()> {
-        conversation::handlers::handle_post_message(conversation_address, message)
+        conversation::handlers::handle_post_message(conversation_address.clone(), message)?;
+        // the draft for this conversation has now been sent
+        draft::handlers::handle_discard_draft(conversation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn forward_message(
+        message_address: Address,
+        source_conversation: Address,
+        target_conversation: Address,
+        timestamp: u64,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_forward_message(
+            message_address,
+            source_conversation,
+            target_conversation,
+            timestamp,
+        )
     }
 
     #[zome_fn("hc_public")]
     pub fn get_messages(
         address: Address,
+        now: Option<u64>,
+        exclude_blocked: Option<bool>,
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<message::Message>>> {
+        conversation::handlers::handle_get_messages(address, now, exclude_blocked.unwrap_or(false))
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn cleanup_expired_messages(
+        conversation_address: Address,
+        now: u64,
+    ) -> ZomeApiResult<Vec<Address>> {
+        conversation::handlers::handle_cleanup_expired_messages(conversation_address, now)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn set_conversation_ttl(
+        conversation_address: Address,
+        default_ttl: Option<u64>,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_set_conversation_ttl(conversation_address, default_ttl)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn set_posting_policy(
+        conversation_address: Address,
+        posting_policy: conversation::PostingPolicy,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_set_posting_policy(conversation_address, posting_policy)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn set_join_policy(
+        conversation_address: Address,
+        join_policy: conversation::JoinPolicy,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_set_join_policy(conversation_address, join_policy)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn set_conversation_tags(
+        conversation_address: Address,
+        tags: Vec<String>,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_set_conversation_tags(conversation_address, tags)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn set_conversation_topic(
+        conversation_address: Address,
+        description: String,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_set_conversation_topic(conversation_address, description)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn delete_message(
+        conversation_address: Address,
+        message_address: Address,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_delete_message(conversation_address, message_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn kick_member(
+        conversation_address: Address,
+        agent_address: Address,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_kick_member(conversation_address, agent_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_audit_log(
+        conversation_address: Address,
+        page: usize,
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<audit::AuditEntry>>> {
+        audit::handlers::handle_get_audit_log(conversation_address, page)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn pin_message(
+        conversation_address: Address,
+        message_address: Address,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_pin_message(conversation_address, message_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn unpin_message(
+        conversation_address: Address,
+        message_address: Address,
+    ) -> ZomeApiResult<()> {
+        conversation::handlers::handle_unpin_message(conversation_address, message_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_pinned_messages(
+        conversation_address: Address,
     ) -> ZomeApiResult<Vec<GetLinksLoadResult<message::Message>>> {
-        conversation::handlers::handle_get_messages(address)
+        conversation::handlers::handle_get_pinned_messages(conversation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_my_mentions() -> ZomeApiResult<Vec<message::Mention>> {
+        message::handlers::handle_get_my_mentions()
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn search_messages(
+        conversation_address: Address,
+        query: String,
+        limit: usize,
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<message::Message>>> {
+        search::handlers::handle_search_messages(conversation_address, query, limit)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn search_conversations(
+        query: String,
+        sort: search::ConversationSort,
+        limit: usize,
+        community: Option<Address>,
+    ) -> ZomeApiResult<Vec<search::ConversationSearchResult>> {
+        search::handlers::handle_search_conversations(query, sort, limit, community)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn update_search_index(conversation_address: Address) -> ZomeApiResult<Option<Address>> {
+        search::handlers::handle_update_search_index(conversation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_messages_by_hashtag(
+        tag: String,
+        now: Option<u64>,
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<message::Message>>> {
+        hashtag::handlers::handle_get_messages_by_hashtag(tag, now)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_trending_hashtags(
+        window: hashtag::TimeWindow,
+    ) -> ZomeApiResult<Vec<hashtag::TrendingHashtag>> {
+        hashtag::handlers::handle_get_trending_hashtags(window)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn upload_attachment_chunk(data: Vec<u8>) -> ZomeApiResult<Address> {
+        attachment::handlers::handle_upload_attachment_chunk(data)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn upload_attachment(
+        name: String,
+        mime_type: String,
+        size: u64,
+        chunks: Vec<Address>,
+    ) -> ZomeApiResult<Address> {
+        attachment::handlers::handle_upload_attachment(name, mime_type, size, chunks)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_attachment(address: Address) -> ZomeApiResult<attachment::AttachmentManifest> {
+        attachment::handlers::handle_get_attachment(address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_attachment_chunk(address: Address) -> ZomeApiResult<attachment::AttachmentChunk> {
+        attachment::handlers::handle_get_attachment_chunk(address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn vote(
+        conversation_address: Address,
+        message_address: Address,
+        option: usize,
+    ) -> ZomeApiResult<()> {
+        poll::handlers::handle_vote(conversation_address, message_address, option)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_poll_results(message_address: Address) -> ZomeApiResult<poll::PollResults> {
+        poll::handlers::handle_get_poll_results(message_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn schedule_message(
+        conversation_address: Address,
+        spec: message::MessageSpec,
+        send_at: u64,
+    ) -> ZomeApiResult<Address> {
+        schedule::handlers::handle_schedule_message(conversation_address, spec, send_at)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn list_scheduled_messages(
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<schedule::ScheduledMessage>>> {
+        schedule::handlers::handle_list_scheduled_messages()
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn cancel_scheduled_message(address: Address) -> ZomeApiResult<()> {
+        schedule::handlers::handle_cancel_scheduled_message(address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn send_scheduled_messages(now: u64) -> ZomeApiResult<Vec<Address>> {
+        schedule::handlers::handle_send_scheduled_messages(now)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn save_draft(
+        conversation_address: Address,
+        message: message::MessageSpec,
+    ) -> ZomeApiResult<Address> {
+        draft::handlers::handle_save_draft(conversation_address, message)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_draft(conversation_address: Address) -> ZomeApiResult<draft::Draft> {
+        draft::handlers::handle_get_draft(conversation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn discard_draft(conversation_address: Address) -> ZomeApiResult<()> {
+        draft::handlers::handle_discard_draft(conversation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn set_notification_level(
+        conversation_address: Address,
+        level: notification::NotificationLevel,
+    ) -> ZomeApiResult<Address> {
+        notification::handlers::handle_set_notification_level(conversation_address, level)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_notification_settings(
+    ) -> ZomeApiResult<Vec<notification::NotificationSetting>> {
+        notification::handlers::handle_get_notification_settings()
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn block_agent(agent_address: Address) -> ZomeApiResult<()> {
+        block::handlers::handle_block_agent(agent_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn unblock_agent(agent_address: Address) -> ZomeApiResult<()> {
+        block::handlers::handle_unblock_agent(agent_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_blocked_agents() -> ZomeApiResult<Vec<Address>> {
+        block::handlers::handle_get_blocked_agents()
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn file_report(
+        conversation_address: Address,
+        subject: report::ReportSubject,
+        reason: report::ReportReason,
+        note: String,
+    ) -> ZomeApiResult<Address> {
+        report::handlers::handle_report(conversation_address, subject, reason, note)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_reports(conversation_address: Address) -> ZomeApiResult<Vec<report::QueuedReport>> {
+        report::handlers::handle_get_reports(conversation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn resolve_report(
+        report_address: Address,
+        action: report::ReportAction,
+        note: String,
+    ) -> ZomeApiResult<Address> {
+        report::handlers::handle_resolve_report(report_address, action, note)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn create_invite(
+        conversation_address: Address,
+        max_uses: u32,
+        expires_at: u64,
+    ) -> ZomeApiResult<String> {
+        invite::handlers::handle_create_invite(conversation_address, max_uses, expires_at)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn redeem_invite(token: String, now: u64) -> ZomeApiResult<()> {
+        invite::handlers::handle_redeem_invite(token, now)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn list_invites(now: u64) -> ZomeApiResult<Vec<invite::InviteStatus>> {
+        invite::handlers::handle_list_invites(now)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn revoke_invite(token: String) -> ZomeApiResult<()> {
+        invite::handlers::handle_revoke_invite(token)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn request_to_join(conversation_address: Address) -> ZomeApiResult<Address> {
+        join_request::handlers::handle_request_to_join(conversation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_join_requests(
+        conversation_address: Address,
+    ) -> ZomeApiResult<Vec<join_request::JoinRequestState>> {
+        join_request::handlers::handle_get_join_requests(conversation_address)
+    }
+
+    // approving does not add the requester, they still have to call join_conversation
+    #[zome_fn("hc_public")]
+    pub fn approve_join(request_address: Address) -> ZomeApiResult<()> {
+        join_request::handlers::handle_approve_join(request_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn deny_join(request_address: Address) -> ZomeApiResult<()> {
+        join_request::handlers::handle_deny_join(request_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn create_category(
+        name: String,
+        position: u64,
+        community: Option<Address>,
+    ) -> ZomeApiResult<Address> {
+        category::handlers::handle_create_category(name, position, community)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn update_category(category_address: Address, name: String, position: u64) -> ZomeApiResult<()> {
+        category::handlers::handle_update_category(category_address, name, position)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn move_conversation_to_category(
+        conversation_address: Address,
+        category_address: Option<Address>,
+    ) -> ZomeApiResult<()> {
+        category::handlers::handle_move_conversation_to_category(conversation_address, category_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_conversation_tree(
+        community: Option<Address>,
+    ) -> ZomeApiResult<category::ConversationTree> {
+        category::handlers::handle_get_conversation_tree(community)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn create_community(name: String, description: String) -> ZomeApiResult<Address> {
+        community::handlers::handle_create_community(name, description)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn join_community(community_address: Address) -> ZomeApiResult<()> {
+        community::handlers::handle_join_community(community_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_all_communities(
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<community::Community>>> {
+        community::handlers::handle_get_all_communities()
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_my_communities(
+    ) -> ZomeApiResult<Vec<GetLinksLoadResult<community::Community>>> {
+        community::handlers::handle_get_my_communities()
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn get_community_members(community_address: Address) -> ZomeApiResult<Vec<Address>> {
+        community::handlers::handle_get_community_members(community_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn add_community_admin(
+        community_address: Address,
+        agent_address: Address,
+    ) -> ZomeApiResult<()> {
+        community::handlers::handle_add_community_admin(community_address, agent_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn remove_community_admin(
+        community_address: Address,
+        agent_address: Address,
+    ) -> ZomeApiResult<()> {
+        community::handlers::handle_remove_community_admin(community_address, agent_address)
+    }
+
+    #[zome_fn("hc_public")]
+    pub fn remove_community_member(
+        community_address: Address,
+        agent_address: Address,
+    ) -> ZomeApiResult<()> {
+        community::handlers::handle_remove_community_member(community_address, agent_address)
     }
 }
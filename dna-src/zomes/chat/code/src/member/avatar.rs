@@ -0,0 +1,290 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::{dna::entry_types::Sharing, validation::EntryValidationData},
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+pub const MAX_AVATAR_SIZE: usize = 256 * 1024;
+pub const MAX_AVATAR_DIMENSION: u32 = 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+/// An avatar image stored in the DHT. The format and dimensions must match what is
+/// read from the image data itself.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct Avatar {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// What the UI should render for a member, preferring an avatar stored in the DHT
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub enum AvatarSource {
+    Stored { address: Address, avatar: Avatar },
+    // profiles registered before avatars were stored only have an external url
+    LegacyUrl(String),
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from(*data.get(at)?) << 8 | u32::from(*data.get(at + 1)?))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(be_u16(data, at)? << 16 | be_u16(data, at + 2)?)
+}
+
+fn le_u16(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from(*data.get(at)?) | u32::from(*data.get(at + 1)?) << 8)
+}
+
+fn le_u24(data: &[u8], at: usize) -> Option<u32> {
+    Some(le_u16(data, at)? | u32::from(*data.get(at + 2)?) << 16)
+}
+
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((be_u32(data, 16)?, be_u32(data, 20)?))
+}
+
+/// Walk the JPEG segments until a start-of-frame marker carrying the dimensions
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut position = 2;
+    loop {
+        if *data.get(position)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(position + 1)?;
+        match marker {
+            0xFF => position += 1,
+            0x01 | 0xD0..=0xD7 => position += 2,
+            0xC0..=0xCF if marker != 0xC4 && marker != 0xC8 && marker != 0xCC => {
+                return Some((be_u16(data, position + 7)?, be_u16(data, position + 5)?));
+            }
+            _ => position += 2 + be_u16(data, position + 2)? as usize,
+        }
+    }
+}
+
+fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    match data.get(12..16)? {
+        b"VP8 " => {
+            if data.get(23..26)? != [0x9D, 0x01, 0x2A] {
+                return None;
+            }
+            Some((le_u16(data, 26)? & 0x3FFF, le_u16(data, 28)? & 0x3FFF))
+        }
+        b"VP8L" => {
+            if *data.get(20)? != 0x2F {
+                return None;
+            }
+            let bits = le_u16(data, 21)? | le_u16(data, 23)? << 16;
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8X" => Some((le_u24(data, 24)? + 1, le_u24(data, 27)? + 1)),
+        _ => None,
+    }
+}
+
+/// Identify the image format from its magic number and read its dimensions from the header
+pub fn inspect_image(data: &[u8]) -> Result<(ImageFormat, u32, u32), String> {
+    let (format, dimensions) = if data.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        (ImageFormat::Png, png_dimensions(data))
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        (ImageFormat::Jpeg, jpeg_dimensions(data))
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(&b"WEBP"[..]) {
+        (ImageFormat::Webp, webp_dimensions(data))
+    } else {
+        return Err("Avatars must be PNG, JPEG or WebP images".into());
+    };
+    let (width, height) =
+        dimensions.ok_or_else(|| String::from("Could not read the avatar image dimensions"))?;
+    Ok((format, width, height))
+}
+
+fn validate_avatar(avatar: &Avatar) -> Result<(), String> {
+    if avatar.data.len() > MAX_AVATAR_SIZE {
+        return Err(format!("Avatars cannot be larger than {} bytes", MAX_AVATAR_SIZE));
+    }
+    let (format, width, height) = inspect_image(&avatar.data)?;
+    if (format, width, height) != (avatar.format, avatar.width, avatar.height) {
+        return Err("Avatar format or dimensions do not match the image data".into());
+    }
+    if width == 0 || height == 0 || width > MAX_AVATAR_DIMENSION || height > MAX_AVATAR_DIMENSION {
+        return Err(format!(
+            "Avatars must be between 1x1 and {}x{} pixels",
+            MAX_AVATAR_DIMENSION, MAX_AVATAR_DIMENSION
+        ));
+    }
+    Ok(())
+}
+
+use crate::AVATAR_ENTRY;
+
+pub fn avatar_definition() -> ValidatingEntryType {
+    entry!(
+        name: AVATAR_ENTRY,
+        description: "An avatar image referenced from a member profile",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<Avatar>| {
+            match validation_data {
+                EntryValidationData::Create{entry, ..} => validate_avatar(&entry),
+                _ => Err("Cannot modify or delete an avatar".into()),
+            }
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 13];
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&[8, 6, 0, 0, 0]);
+        data
+    }
+
+    fn jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        data.extend_from_slice(b"JFIF\0");
+        data.extend_from_slice(&[1, 1, 0, 0, 1, 0, 1, 0, 0]);
+        data.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 0x08]);
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&[3, 1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1]);
+        data
+    }
+
+    fn webp(chunk: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = b"RIFF".to_vec();
+        data.extend_from_slice(&((body.len() + 12) as u32).to_le_bytes());
+        data.extend_from_slice(b"WEBP");
+        data.extend_from_slice(chunk);
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn webp_lossy(width: u16, height: u16) -> Vec<u8> {
+        let mut body = vec![0x30, 0x01, 0x00, 0x9D, 0x01, 0x2A];
+        body.extend_from_slice(&width.to_le_bytes());
+        body.extend_from_slice(&height.to_le_bytes());
+        webp(b"VP8 ", &body)
+    }
+
+    fn webp_lossless(width: u32, height: u32) -> Vec<u8> {
+        let bits = (width - 1) | (height - 1) << 14;
+        let mut body = vec![0x2F];
+        body.extend_from_slice(&bits.to_le_bytes());
+        webp(b"VP8L", &body)
+    }
+
+    fn webp_extended(width: u32, height: u32) -> Vec<u8> {
+        let mut body = vec![0; 4];
+        body.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+        body.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+        webp(b"VP8X", &body)
+    }
+
+    fn fixtures() -> Vec<Vec<u8>> {
+        vec![
+            png(32, 16),
+            jpeg(32, 16),
+            webp_lossy(32, 16),
+            webp_lossless(32, 16),
+            webp_extended(32, 16),
+        ]
+    }
+
+    #[test]
+    fn reads_format_and_dimensions() {
+        assert_eq!(inspect_image(&png(32, 16)), Ok((ImageFormat::Png, 32, 16)));
+        assert_eq!(inspect_image(&jpeg(32, 16)), Ok((ImageFormat::Jpeg, 32, 16)));
+        assert_eq!(inspect_image(&webp_lossy(32, 16)), Ok((ImageFormat::Webp, 32, 16)));
+        assert_eq!(inspect_image(&webp_lossless(32, 16)), Ok((ImageFormat::Webp, 32, 16)));
+        assert_eq!(inspect_image(&webp_extended(32, 16)), Ok((ImageFormat::Webp, 32, 16)));
+    }
+
+    #[test]
+    fn skips_jpeg_segments_before_the_frame() {
+        let mut data = jpeg(32, 16);
+        // a DHT segment and fill bytes ahead of the start-of-frame marker
+        let extra = [0xFF, 0xC4, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xFF];
+        data.splice(20..20, extra.iter().cloned());
+        assert_eq!(jpeg_dimensions(&data), Some((32, 16)));
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(inspect_image(&[]).is_err());
+        assert!(inspect_image(b"GIF89a\x01\x00\x01\x00").is_err());
+        assert!(inspect_image(&webp(b"ALPH", &[0; 10])).is_err());
+    }
+
+    #[test]
+    fn truncated_images_do_not_panic() {
+        for data in fixtures() {
+            let complete = inspect_image(&data);
+            for length in 0..data.len() {
+                // cutting off bytes after the dimensions is harmless, anything else must fail
+                let truncated = inspect_image(&data[..length]);
+                assert!(truncated.is_err() || truncated == complete);
+            }
+            assert!(inspect_image(&data[..data.len() / 2]).is_err());
+        }
+    }
+
+    #[test]
+    fn malformed_images_do_not_panic() {
+        for data in fixtures() {
+            for position in 0..data.len() {
+                for value in &[0x00, 0x01, 0x7F, 0xFF] {
+                    let mut corrupted = data.clone();
+                    corrupted[position] = *value;
+                    let _ = inspect_image(&corrupted);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jpeg_with_bad_markers_is_rejected() {
+        let mut data = jpeg(32, 16);
+        data[2] = 0x00;
+        assert_eq!(jpeg_dimensions(&data), None);
+        // a segment length pointing past the end of the data
+        let mut data = jpeg(32, 16);
+        data[4] = 0xFF;
+        assert_eq!(jpeg_dimensions(&data), None);
+    }
+
+    #[test]
+    fn webp_without_signatures_is_rejected() {
+        let mut data = webp_lossy(32, 16);
+        data[23] = 0x00;
+        assert_eq!(webp_dimensions(&data), None);
+        let mut data = webp_lossless(32, 16);
+        data[20] = 0x00;
+        assert_eq!(webp_dimensions(&data), None);
+    }
+}
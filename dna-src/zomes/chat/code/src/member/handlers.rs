@@ -1,4 +1,7 @@
-use crate::member::Profile;
+use crate::member::{
+    avatar::{inspect_image, Avatar, AvatarSource},
+    Profile,
+};
 use hdk::{
     error::{ZomeApiError, ZomeApiResult},
     holochain_core_types::{entry::Entry, link::LinkMatch},
@@ -6,11 +9,17 @@ use hdk::{
     holochain_persistence_api::cas::content::Address,
     AGENT_ADDRESS,
     prelude::{QueryResult, QueryArgsOptions},
+    utils::get_as_type,
 };
 use crate::utils::{get_links_and_load_type, GetLinksLoadResult};
 use std::convert::TryFrom;
+use crate::AVATAR_ENTRY;
 
-pub fn handle_register(name: String, avatar_url: String) -> ZomeApiResult<Address> {
+pub fn handle_register(
+    name: String,
+    avatar_url: String,
+    avatar: Option<Address>,
+) -> ZomeApiResult<Address> {
     let anchor_entry = Entry::App("anchor".into(), RawString::from("member_directory").into());
 
     let anchor_address = hdk::commit_entry(&anchor_entry)?;
@@ -22,6 +31,7 @@ pub fn handle_register(name: String, avatar_url: String) -> ZomeApiResult<Addres
             name,
             avatar_url,
             address: AGENT_ADDRESS.to_string().into(),
+            avatar,
         }
         .into(),
     );
@@ -62,3 +72,28 @@ pub fn handle_get_my_member_profile() -> ZomeApiResult<Profile> {
         unreachable!()
     }
 }
+
+/// Store an avatar image so it can be referenced from a profile
+pub fn handle_upload_avatar(data: Vec<u8>) -> ZomeApiResult<Address> {
+    let (format, width, height) = inspect_image(&data).map_err(ZomeApiError::Internal)?;
+    let avatar = Avatar {
+        format,
+        width,
+        height,
+        data,
+    };
+    hdk::commit_entry(&Entry::App(AVATAR_ENTRY.into(), avatar.into()))
+}
+
+/// The agent's stored avatar, falling back to the external url of older profiles
+pub fn handle_get_avatar(agent_address: Address) -> ZomeApiResult<AvatarSource> {
+    let profile = handle_get_member_profile(agent_address)?;
+    match profile.avatar {
+        Some(address) => Ok(AvatarSource::Stored {
+            avatar: get_as_type(address.clone())?,
+            address,
+        }),
+        None if !profile.avatar_url.is_empty() => Ok(AvatarSource::LegacyUrl(profile.avatar_url)),
+        None => Err(ZomeApiError::Internal("Agent does not have an avatar".into())),
+    }
+}
@@ -1,10 +1,12 @@
 use hdk::{
     entry_definition::ValidatingEntryType,
-    holochain_core_types::dna::entry_types::Sharing,
+    holochain_core_types::{dna::entry_types::Sharing, validation::EntryValidationData},
     holochain_json_api::{error::JsonError, json::JsonString},
     holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
 };
 
+pub mod avatar;
 pub mod handlers;
 
 #[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
@@ -19,6 +21,17 @@ pub struct Profile {
     pub name: String,
     pub avatar_url: String,
     pub address: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Address>,
+}
+
+fn validate_profile(profile: &Profile) -> Result<(), String> {
+    match &profile.avatar {
+        Some(avatar_address) => get_as_type::<avatar::Avatar>(avatar_address.clone())
+            .map(|_| ())
+            .map_err(|_| format!("Avatar {} could not be found", avatar_address)),
+        None => Ok(()),
+    }
 }
 
 pub fn profile_definition() -> ValidatingEntryType {
@@ -31,8 +44,12 @@ pub fn profile_definition() -> ValidatingEntryType {
             hdk::ValidationPackageDefinition::Entry
         },
 
-        validation: |_validation_data: hdk::EntryValidationData<Profile>| {
-            Ok(())
+        validation: |validation_data: hdk::EntryValidationData<Profile>| {
+            match validation_data {
+                EntryValidationData::Create{entry, ..} => validate_profile(&entry),
+                EntryValidationData::Modify{new_entry, ..} => validate_profile(&new_entry),
+                _ => Ok(()),
+            }
         },
 
         links: [
@@ -0,0 +1,39 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    AGENT_ADDRESS,
+};
+use std::{collections::HashSet, convert::TryFrom};
+use crate::block::handlers::handle_get_blocked_agents;
+use crate::message::{Message, Mention};
+use crate::MENTIONS_LINK_TYPE;
+
+/// All messages that mention this agent. The conversation each was posted in is carried in the link tag.
+/// Any mentions that fail to load or were made by agents this agent has blocked are dropped.
+pub fn handle_get_my_mentions() -> ZomeApiResult<Vec<Mention>> {
+    let links = hdk::get_links(
+        &AGENT_ADDRESS,
+        LinkMatch::Exactly(MENTIONS_LINK_TYPE),
+        LinkMatch::Any,
+    )?
+    .links();
+    let blocked: HashSet<String> = handle_get_blocked_agents()?
+        .iter()
+        .map(|agent_address| agent_address.to_string())
+        .collect();
+
+    Ok(links
+        .into_iter()
+        .filter_map(|link| match hdk::get_entry(&link.address) {
+            Ok(Some(Entry::App(_, entry_value))) => Message::try_from(entry_value)
+                .ok()
+                .filter(|message| !blocked.contains(&message.author))
+                .map(|message| Mention {
+                    conversation_address: link.tag.into(),
+                    message_address: link.address,
+                    message,
+                }),
+            _ => None,
+        })
+        .collect())
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// Prefix that marks a message type as a client defined extension, e.g. `x-acme/sticker`
+pub const EXTENSION_PREFIX: &str = "x-";
+pub const MAX_EXTENSION_LENGTH: usize = 64;
+
+/// The kinds of message the zome knows how to validate. Anything else must be
+/// namespaced as an extension so clients can safely ignore types they do not understand.
+/// Serialized as a plain string so existing `"text"` messages are still readable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageType {
+    Text,
+    Markdown,
+    Image,
+    File,
+    System,
+    Poll,
+    Extension(String),
+    // a stored type this version does not recognise. Kept so the entry can still be read,
+    // but new messages of an unknown type are rejected by validation.
+    Unknown(String),
+}
+
+fn is_extension_part(part: &str) -> bool {
+    !part.is_empty()
+        && part
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_' || c == '.')
+}
+
+impl FromStr for MessageType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(MessageType::Text),
+            "markdown" => Ok(MessageType::Markdown),
+            "image" => Ok(MessageType::Image),
+            "file" => Ok(MessageType::File),
+            "system" => Ok(MessageType::System),
+            "poll" => Ok(MessageType::Poll),
+            extension if extension.starts_with(EXTENSION_PREFIX) => {
+                let mut parts = extension[EXTENSION_PREFIX.len()..].split('/');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(namespace), Some(name), None)
+                        if extension.len() <= MAX_EXTENSION_LENGTH
+                            && is_extension_part(namespace)
+                            && is_extension_part(name) =>
+                    {
+                        Ok(MessageType::Extension(extension.to_owned()))
+                    }
+                    _ => Err(format!(
+                        "Invalid extension message type {}, expected {}<namespace>/<name>",
+                        extension, EXTENSION_PREFIX
+                    )),
+                }
+            }
+            unknown => Err(format!("Unknown message type {}", unknown)),
+        }
+    }
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MessageType::Text => "text",
+            MessageType::Markdown => "markdown",
+            MessageType::Image => "image",
+            MessageType::File => "file",
+            MessageType::System => "system",
+            MessageType::Poll => "poll",
+            MessageType::Extension(name) | MessageType::Unknown(name) => name,
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Serialize for MessageType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(name.parse().unwrap_or(MessageType::Unknown(name)))
+    }
+}
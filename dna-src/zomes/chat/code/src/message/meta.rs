@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+pub const META_VERSION: u32 = 1;
+pub const MAX_META_KEYS: usize = 32;
+pub const MAX_META_KEY_LENGTH: usize = 64;
+pub const MAX_META_SIZE: usize = 2048;
+
+/// Plain string meta from before structured meta existed is still accepted while
+/// clients migrate. Turning this off closes the migration window.
+pub const ACCEPT_LEGACY_META: bool = true;
+
+/// Versioned key/value metadata attached to a message. Keys are shared between clients,
+/// e.g. `format` for formatting hints, `client` to identify the sending client or
+/// `link_preview` for a preview of a link in the payload. Clients ignore keys they do not know.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageMeta {
+    pub version: u32,
+    #[serde(default)]
+    pub fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl Default for MessageMeta {
+    fn default() -> Self {
+        MessageMeta {
+            version: META_VERSION,
+            fields: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Meta {
+    Structured(MessageMeta),
+    Legacy(String),
+}
+
+impl Default for Meta {
+    fn default() -> Self {
+        Meta::Structured(MessageMeta::default())
+    }
+}
+
+impl Meta {
+    /// The structured form of the meta. Legacy strings are used if they happen to hold structured meta JSON.
+    pub fn structured(&self) -> Option<MessageMeta> {
+        match self {
+            Meta::Structured(meta) => Some(meta.clone()),
+            Meta::Legacy(legacy) => serde_json::from_str(legacy).ok(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Meta::Structured(meta) => {
+                if meta.version == 0 || meta.version > META_VERSION {
+                    return Err(format!("Unsupported meta version {}", meta.version));
+                }
+                if meta.fields.len() > MAX_META_KEYS {
+                    return Err(format!("Meta can have at most {} keys", MAX_META_KEYS));
+                }
+                let invalid_key = meta.fields.keys().find(|key| {
+                    key.is_empty()
+                        || key.len() > MAX_META_KEY_LENGTH
+                        || !key.chars().all(|c| {
+                            c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-' || c == '.'
+                        })
+                });
+                if let Some(key) = invalid_key {
+                    return Err(format!("Invalid meta key {}", key));
+                }
+                let size = serde_json::to_string(meta).map(|json| json.len()).unwrap_or(usize::max_value());
+                if size > MAX_META_SIZE {
+                    return Err(format!("Meta cannot be larger than {} bytes", MAX_META_SIZE));
+                }
+                Ok(())
+            }
+            Meta::Legacy(legacy) => {
+                if !ACCEPT_LEGACY_META {
+                    return Err("Plain string meta is no longer accepted".into());
+                }
+                if legacy.len() > MAX_META_SIZE {
+                    return Err(format!("Meta cannot be larger than {} bytes", MAX_META_SIZE));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Meta {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn reads_structured_and_legacy_meta() {
+        let structured = parse(r#"{"version": 1, "fields": {"client": "web"}}"#);
+        assert_eq!(structured.structured().unwrap().fields["client"], "web");
+        assert!(structured.validate().is_ok());
+
+        let legacy = parse(r#""{}""#);
+        assert_eq!(legacy, Meta::Legacy("{}".into()));
+        assert!(legacy.validate().is_ok());
+        assert_eq!(parse(r#""plain text""#).structured(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_structured_meta() {
+        assert!(parse(r#"{"version": 0}"#).validate().is_err());
+        assert!(parse(r#"{"version": 2}"#).validate().is_err());
+        assert!(parse(r#"{"version": 1, "fields": {"Bad Key": 1}}"#).validate().is_err());
+        let too_large = format!(r#"{{"version": 1, "fields": {{"note": "{}"}}}}"#, "a".repeat(MAX_META_SIZE));
+        assert!(parse(&too_large).validate().is_err());
+        assert!(Meta::Legacy("a".repeat(MAX_META_SIZE + 1)).validate().is_err());
+    }
+}
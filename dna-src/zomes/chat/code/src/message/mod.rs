@@ -4,48 +4,151 @@ use hdk::{
     holochain_core_types::{
         dna::entry_types::Sharing,
         validation::EntryValidationData,
-        // entry::Entry,
+        entry::Entry,
     },
     holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
 };
+use std::{collections::HashSet, convert::TryFrom};
 
 use validator::Validate;
 
+pub mod handlers;
+pub mod message_type;
+pub mod meta;
+pub mod reference;
+
+pub use self::message_type::MessageType;
+pub use self::meta::Meta;
+pub use self::reference::{MessageReference, MessageSource, ReferenceKind};
+
 /// This struct is serialized internally to a message entry. All message entries
 /// must be serializable to this struct to be valid
 #[derive(Serialize, Deserialize, Debug, Clone, DefaultJson, Validate, PartialEq)]
 pub struct Message {
     pub timestamp: u64,
     pub author: String,
-    pub message_type: String,
+    pub message_type: MessageType,
     #[validate(length(min = 1, max = 1024))]
     pub payload: String,
-    pub meta: String,
+    pub meta: Meta,
+    // skipped when empty so messages without mentions keep the same entry address as before
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mentions: Vec<Address>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[validate(length(max = 10))]
+    pub attachments: Vec<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    // the message this one forwards or quotes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<MessageReference>,
 }
 
 impl Message {
     pub fn from_spec(spec: &MessageSpec, author: &String) -> Message {
+        let mut seen = HashSet::new();
         return Message {
             message_type: spec.message_type.clone(),
             payload: spec.payload.clone(),
             meta: spec.meta.clone(),
             author: author.to_owned(),
             timestamp: spec.timestamp.clone(),
+            mentions: spec.mentions.iter().filter(|a| seen.insert(*a)).cloned().collect(),
+            attachments: spec.attachments.clone(),
+            expires_at: spec.expires_at,
+            reference: None,
         };
     }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.map(|expires_at| expires_at <= now).unwrap_or(false)
+    }
 }
 
 /// This is the data needed to create a Message entry (e.g. the specification of a message)
 /// but not the actual message entry itself
 #[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
 pub struct MessageSpec {
-    pub message_type: String,
+    pub message_type: MessageType,
     pub timestamp: u64,
     pub payload: String,
-    pub meta: String,
+    #[serde(default)]
+    pub meta: Meta,
+    #[serde(default)]
+    pub mentions: Vec<Address>,
+    #[serde(default)]
+    pub attachments: Vec<Address>,
+    // falls back to the conversation's default time to live when not given
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    // posts the message as a reply quoting this one
+    #[serde(default)]
+    pub quote: Option<MessageSource>,
+}
+
+/// A message in which an agent was mentioned, along with the conversation it was posted to
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+#[serde(rename_all = "camelCase")]
+pub struct Mention {
+    pub conversation_address: Address,
+    pub message_address: Address,
+    pub message: Message,
+}
+
+/// Every mentioned address must resolve to an agent in the DHT
+fn validate_mentions(message: &Message) -> Result<(), String> {
+    for agent_address in &message.mentions {
+        match hdk::get_entry(agent_address) {
+            Ok(Some(Entry::AgentId(_))) => (),
+            _ => return Err(format!("Mentioned address {} is not a known agent", agent_address)),
+        }
+    }
+    Ok(())
+}
+
+/// Every attachment must refer to an attachment manifest that exists
+fn load_attachments(message: &Message) -> Result<Vec<AttachmentManifest>, String> {
+    message.attachments
+        .iter()
+        .map(|attachment_address| {
+            get_as_type::<AttachmentManifest>(attachment_address.clone())
+                .map_err(|_| format!("Attachment {} could not be found", attachment_address))
+        })
+        .collect()
+}
+
+/// Checks specific to each message type, on top of those every message must pass
+fn validate_message_type(message: &Message, attachments: &[AttachmentManifest]) -> Result<(), String> {
+    match message.message_type {
+        MessageType::Image => {
+            if attachments.is_empty() || !attachments.iter().all(|a| a.mime_type.starts_with("image/")) {
+                return Err("Image messages must attach one or more images".into());
+            }
+        },
+        MessageType::File => {
+            if attachments.is_empty() {
+                return Err("File messages must have at least one attachment".into());
+            }
+        },
+        MessageType::System => {
+            if !message.mentions.is_empty() || !attachments.is_empty() {
+                return Err("System messages cannot mention agents or have attachments".into());
+            }
+        },
+        MessageType::Poll => {
+            Poll::from_message(message)?.validate(message.timestamp)?;
+        },
+        MessageType::Text | MessageType::Markdown | MessageType::Extension(_) => (),
+        MessageType::Unknown(ref name) => {
+            return name.parse::<MessageType>().map(|_| ());
+        },
+    }
+    Ok(())
 }
 
-use crate::MESSAGE_ENTRY;
+use crate::{attachment::AttachmentManifest, poll::Poll, utils::header_time, MENTIONS_LINK_TYPE, MESSAGE_ENTRY};
 
 pub fn message_definition() -> ValidatingEntryType {
     entry!(
@@ -58,17 +161,71 @@ pub fn message_definition() -> ValidatingEntryType {
         },
         validation: | validation_data: hdk::EntryValidationData<Message>| {
             match validation_data {
-                EntryValidationData::Create{entry, ..} => {
+                EntryValidationData::Create{entry, validation_data} => {
                     let new_message = Message::from(entry);
-                    match new_message.validate() {
-                      Ok(_) => Ok(()),
-                      Err(e) => Err(e.to_string())
+                    let sources = validation_data.sources();
+                    if !sources.contains(&Address::from(new_message.author.clone())) {
+                        return Err("Messages can only be committed by their author".into());
+                    }
+                    new_message.validate().map_err(|e| e.to_string())?;
+                    new_message.meta.validate()?;
+                    validate_mentions(&new_message)?;
+                    let attachments = load_attachments(&new_message)?;
+                    validate_message_type(&new_message, &attachments)?;
+                    if let Some(reference) = &new_message.reference {
+                        reference::validate_reference(&new_message, reference, &sources, header_time(&validation_data)?)?;
+                    }
+                    match new_message.expires_at {
+                        Some(expires_at) if expires_at <= new_message.timestamp => {
+                            Err("A message cannot expire before it is posted".into())
+                        },
+                        _ => Ok(()),
+                    }
+                },
+                // ephemeral messages are removed by their author once they expire
+                EntryValidationData::Delete{old_entry, validation_data, ..} => {
+                    let author = Address::from(old_entry.author.clone());
+                    if old_entry.expires_at.is_some() && validation_data.sources().contains(&author) {
+                        Ok(())
+                    } else {
+                        Err("Only the author can delete a message and only if it expires".into())
                     }
                 },
                 _ => {
-                    Err("Cannot modify or delete a message".into())
+                    Err("Cannot modify a message".into())
                 }
             }
-        }
+        },
+
+        links: [
+            from!(
+                "%agent_id",
+                link_type: MENTIONS_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                // an agent can only be linked to messages that actually mention them
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let link = match validation_data {
+                        hdk::LinkValidationData::LinkAdd { link, .. } => link,
+                        hdk::LinkValidationData::LinkRemove { link, .. } => link,
+                    };
+                    match hdk::get_entry(link.link.target()) {
+                        Ok(Some(Entry::App(_, entry_value))) => {
+                            let message = Message::try_from(entry_value)
+                                .map_err(|_| String::from("Mention link target is not a message"))?;
+                            if message.mentions.contains(link.link.base()) {
+                                Ok(())
+                            } else {
+                                Err("Message does not mention this agent".into())
+                            }
+                        },
+                        _ => Err("Mention link target is not a message".into()),
+                    }
+                }
+            )
+        ]
     )
 }
@@ -0,0 +1,100 @@
+use hdk::{
+    self,
+    holochain_core_types::link::LinkMatch,
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+
+use crate::conversation::conversation_at;
+use crate::message::Message;
+use crate::{MESSAGE_LINK_TYPE_TO, PUBLIC_STREAM_LINK_TYPE_TO};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    // the original is reposted as it was
+    Forward,
+    // the new message replies to the original
+    Quote,
+}
+
+/// Where a message that is being quoted or forwarded was posted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageSource {
+    pub conversation_address: Address,
+    pub message_address: Address,
+}
+
+/// A message forwarded or quoted by another, attributed to its original author and time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageReference {
+    pub kind: ReferenceKind,
+    pub conversation_address: Address,
+    pub message_address: Address,
+    pub author: String,
+    pub timestamp: u64,
+}
+
+impl MessageReference {
+    pub fn new(kind: ReferenceKind, source: MessageSource, original: &Message) -> MessageReference {
+        MessageReference {
+            kind,
+            conversation_address: source.conversation_address,
+            message_address: source.message_address,
+            author: original.author.clone(),
+            timestamp: original.timestamp,
+        }
+    }
+}
+
+/// Whether any of the agents could see a conversation's messages at the given time. Anyone can
+/// read an open conversation, others are only readable by their members and moderators.
+fn can_read(conversation_address: &Address, agents: &[Address], at: u64) -> Result<bool, String> {
+    let conversation = conversation_at(conversation_address, at)
+        .map_err(|_| String::from("Referenced conversation could not be found"))?;
+    if conversation.join_policy.is_open() || agents.iter().any(|agent| conversation.was_moderated_by(agent, at)) {
+        return Ok(true);
+    }
+    let members = hdk::get_links(conversation_address, LinkMatch::Exactly(PUBLIC_STREAM_LINK_TYPE_TO), LinkMatch::Any)
+        .map_err(|_| String::from("Could not load the conversation's members"))?
+        .addresses();
+    Ok(agents.iter().any(|agent| members.contains(agent)))
+}
+
+/// Checks that a referenced message exists, is attributed correctly and that the agents
+/// who committed the new message could see it at the time of their header
+pub fn validate_reference(
+    message: &Message,
+    reference: &MessageReference,
+    sources: &[Address],
+    posted_at: u64,
+) -> Result<(), String> {
+    let original: Message = get_as_type(reference.message_address.clone())
+        .map_err(|_| String::from("Referenced message could not be found"))?;
+    if original.author != reference.author || original.timestamp != reference.timestamp {
+        return Err("Referenced message must be attributed to its original author and time".into());
+    }
+    if original.is_expired(posted_at) {
+        return Err("Referenced message had expired".into());
+    }
+    let posted_in_source = hdk::get_links(
+        &reference.conversation_address,
+        LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+        LinkMatch::Any,
+    )
+    .map_err(|_| String::from("Could not load the conversation's messages"))?
+    .addresses()
+    .contains(&reference.message_address);
+    if !posted_in_source {
+        return Err("Referenced message was not posted in the given conversation".into());
+    }
+    if !can_read(&reference.conversation_address, sources, posted_at)? {
+        return Err("Only messages visible to the author can be referenced".into());
+    }
+    if reference.kind == ReferenceKind::Forward
+        && (message.payload != original.payload || message.message_type != original.message_type)
+    {
+        return Err("Forwarded messages must keep the original content".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::entry::Entry,
+    holochain_persistence_api::cas::content::Address,
+};
+use std::collections::HashSet;
+use crate::notification::{NotificationLevel, NotificationSetting};
+use crate::utils::query_local_entries;
+use crate::{DirectMessage, NOTIFICATION_SETTING_ENTRY};
+
+pub fn handle_set_notification_level(
+    conversation_address: Address,
+    level: NotificationLevel,
+) -> ZomeApiResult<Address> {
+    let setting = NotificationSetting {
+        conversation_address,
+        level,
+    };
+    hdk::commit_entry(&Entry::App(NOTIFICATION_SETTING_ENTRY.into(), setting.into()))
+}
+
+/// The current setting for every conversation this agent has changed from the default
+pub fn handle_get_notification_settings() -> ZomeApiResult<Vec<NotificationSetting>> {
+    let mut settings: Vec<NotificationSetting> =
+        query_local_entries::<NotificationSetting>(NOTIFICATION_SETTING_ENTRY)?
+            .into_iter()
+            .rev()
+            .map(|result| result.entry)
+            .collect();
+    let mut uniques = HashSet::new();
+    settings.retain(|setting| uniques.insert(setting.conversation_address.clone()));
+    Ok(settings)
+}
+
+fn notification_level(conversation_address: &Address) -> ZomeApiResult<NotificationLevel> {
+    Ok(handle_get_notification_settings()?
+        .into_iter()
+        .find(|setting| &setting.conversation_address == conversation_address)
+        .map(|setting| setting.level)
+        .unwrap_or(NotificationLevel::All))
+}
+
+/// Whether a direct message should be passed on to the UI, going by the agent's setting for
+/// the conversation it is about. Muting a conversation leaves only mentions, turning its
+/// notifications off silences it completely.
+pub(crate) fn should_signal(message: &DirectMessage) -> bool {
+    let (conversation_address, is_mention) = match message {
+        DirectMessage::ChannelMessageNotification(payload) => (&payload.conversation_address, false),
+        DirectMessage::JoinChannelNotification(payload) => (&payload.conversation_address, false),
+        DirectMessage::MentionNotification(payload) => (&payload.conversation_address, true),
+        DirectMessage::PollTallyNotification(payload) => (&payload.conversation_address, false),
+        DirectMessage::JoinRequestNotification(payload) => (&payload.conversation_address, false),
+    };
+    match notification_level(conversation_address).unwrap_or(NotificationLevel::All) {
+        NotificationLevel::All => true,
+        NotificationLevel::MentionsOnly => is_mention,
+        NotificationLevel::None => false,
+    }
+}
@@ -0,0 +1,43 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::dna::entry_types::Sharing,
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+pub mod handlers;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationLevel {
+    All,
+    // only the dedicated mention signal is raised
+    MentionsOnly,
+    None,
+}
+
+/// How an agent wants to be notified about a conversation. Kept privately on their
+/// own chain, the latest setting for a conversation wins.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct NotificationSetting {
+    pub conversation_address: Address,
+    pub level: NotificationLevel,
+}
+
+use crate::NOTIFICATION_SETTING_ENTRY;
+
+pub fn notification_setting_definition() -> ValidatingEntryType {
+    entry!(
+        name: NOTIFICATION_SETTING_ENTRY,
+        description: "An agent's notification preference for a conversation",
+        sharing: Sharing::Private,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |_validation_data: hdk::EntryValidationData<NotificationSetting>| {
+            Ok(())
+        }
+    )
+}
@@ -0,0 +1,93 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+    AGENT_ADDRESS,
+};
+use std::collections::{HashMap, HashSet};
+use crate::conversation::handlers::notify_conversation;
+use crate::message::Message;
+use crate::poll::{Poll, PollOptionResult, PollResults, Vote};
+use crate::utils::query_local_entries;
+use crate::{DirectMessage, PollTallySignalPayload, VOTE_ENTRY, VOTE_LINK_TYPE};
+
+/// Vote on a poll, changing this agent's previous vote if they already voted.
+/// The first vote commits the agent's blank ballot and links it from the poll.
+/// The new tally is sent to every member of the conversation.
+pub fn handle_vote(
+    conversation_address: Address,
+    message_address: Address,
+    option: usize,
+) -> ZomeApiResult<()> {
+    let voter: Address = AGENT_ADDRESS.to_string().into();
+    let previous_vote = query_local_entries::<Vote>(VOTE_ENTRY)?
+        .into_iter()
+        .filter(|result| result.entry.poll == message_address)
+        .last();
+    let previous_address = match previous_vote {
+        Some(previous) => previous.address,
+        None => {
+            let ballot_address =
+                hdk::commit_entry(&Vote::ballot_entry(message_address.clone(), voter.clone()))?;
+            hdk::link_entries(&message_address, &ballot_address, VOTE_LINK_TYPE, "")?;
+            ballot_address
+        }
+    };
+    let vote = Vote {
+        poll: message_address.clone(),
+        option: Some(option),
+        voter,
+    };
+    hdk::update_entry(Entry::App(VOTE_ENTRY.into(), vote.into()), &previous_address)?;
+
+    let results = handle_get_poll_results(message_address.clone())?;
+    notify_conversation(
+        conversation_address.clone(),
+        DirectMessage::PollTallyNotification(PollTallySignalPayload {
+            conversation_address,
+            message_address,
+            results,
+        }),
+    )
+}
+
+/// Count the latest vote of every agent on a poll. Loading a ballot returns its latest update.
+pub fn handle_get_poll_results(message_address: Address) -> ZomeApiResult<PollResults> {
+    let message: Message = get_as_type(message_address.clone())?;
+    let poll = Poll::from_message(&message).map_err(ZomeApiError::Internal)?;
+
+    let vote_addresses: HashSet<Address> = hdk::get_links(
+        &message_address,
+        LinkMatch::Exactly(VOTE_LINK_TYPE),
+        LinkMatch::Any,
+    )?
+    .addresses()
+    .into_iter()
+    .collect();
+
+    let latest_votes: HashMap<Address, Vote> = vote_addresses
+        .into_iter()
+        .filter_map(|address| get_as_type::<Vote>(address).ok())
+        .map(|vote| (vote.voter.clone(), vote))
+        .collect();
+
+    let mut counts = vec![0; poll.options.len()];
+    for option in latest_votes.values().filter_map(|vote| vote.option) {
+        if let Some(count) = counts.get_mut(option) {
+            *count += 1;
+        }
+    }
+
+    Ok(PollResults {
+        question: poll.question,
+        closes_at: poll.closes_at,
+        total_votes: counts.iter().sum(),
+        options: poll
+            .options
+            .into_iter()
+            .zip(counts)
+            .map(|(option, votes)| PollOptionResult { option, votes })
+            .collect(),
+    })
+}
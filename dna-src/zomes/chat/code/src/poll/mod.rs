@@ -0,0 +1,170 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    error::ZomeApiResult,
+    holochain_core_types::{dna::entry_types::Sharing, entry::Entry, validation::EntryValidationData},
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+
+use crate::message::{Message, MessageType};
+use crate::utils::{header_time, link_and_sources};
+
+pub mod handlers;
+
+pub const MAX_QUESTION_LENGTH: usize = 256;
+pub const MAX_OPTION_LENGTH: usize = 128;
+pub const MAX_OPTIONS: usize = 10;
+
+/// The payload of a poll message
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<String>,
+    pub closes_at: u64,
+}
+
+impl Poll {
+    pub fn from_message(message: &Message) -> Result<Poll, String> {
+        if message.message_type != MessageType::Poll {
+            return Err("Message is not a poll".into());
+        }
+        serde_json::from_str(&message.payload).map_err(|e| format!("Invalid poll: {}", e))
+    }
+
+    /// Checks a poll posted in a message with the given timestamp
+    pub fn validate(&self, posted_at: u64) -> Result<(), String> {
+        if self.question.is_empty() || self.question.chars().count() > MAX_QUESTION_LENGTH {
+            return Err(format!("Poll questions must have 1-{} characters", MAX_QUESTION_LENGTH));
+        }
+        if self.options.len() < 2 || self.options.len() > MAX_OPTIONS {
+            return Err(format!("Polls must have 2-{} options", MAX_OPTIONS));
+        }
+        if self.options.iter().any(|option| option.is_empty() || option.chars().count() > MAX_OPTION_LENGTH) {
+            return Err(format!("Poll options must have 1-{} characters", MAX_OPTION_LENGTH));
+        }
+        if self.closes_at <= posted_at {
+            return Err("Polls must close after they are posted".into());
+        }
+        Ok(())
+    }
+}
+
+/// An agent's vote on a poll. Every agent's vote starts as a blank ballot whose address only
+/// depends on the poll and the voter, so each agent has exactly one. Voting updates the ballot,
+/// which is allowed until the poll closes.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct Vote {
+    pub poll: Address,
+    pub option: Option<usize>,
+    pub voter: Address,
+}
+
+impl Vote {
+    pub fn ballot(poll: Address, voter: Address) -> Vote {
+        Vote { poll, option: None, voter }
+    }
+
+    pub fn ballot_entry(poll: Address, voter: Address) -> Entry {
+        Entry::App(VOTE_ENTRY.into(), Vote::ballot(poll, voter).into())
+    }
+
+    pub fn ballot_address(poll: &Address, voter: &Address) -> ZomeApiResult<Address> {
+        hdk::entry_address(&Vote::ballot_entry(poll.clone(), voter.clone()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson, PartialEq)]
+pub struct PollOptionResult {
+    pub option: String,
+    pub votes: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson, PartialEq)]
+pub struct PollResults {
+    pub question: String,
+    pub closes_at: u64,
+    pub options: Vec<PollOptionResult>,
+    pub total_votes: usize,
+}
+
+fn load_poll(vote: &Vote, sources: &[Address]) -> Result<Poll, String> {
+    if !sources.contains(&vote.voter) {
+        return Err("Agents can only vote for themselves".into());
+    }
+    let message: Message = get_as_type(vote.poll.clone())
+        .map_err(|_| String::from("Poll could not be found"))?;
+    Poll::from_message(&message)
+}
+
+/// A vote is cast by updating the ballot before the poll closes, going by the time in the
+/// header of the update rather than any time the voter could choose
+fn validate_vote(vote: &Vote, sources: &[Address], voted_at: u64) -> Result<(), String> {
+    let poll = load_poll(vote, sources)?;
+    match vote.option {
+        Some(option) if option < poll.options.len() => (),
+        _ => return Err("Vote is not for one of the poll options".into()),
+    }
+    if voted_at > poll.closes_at {
+        return Err("Poll is closed".into());
+    }
+    Ok(())
+}
+
+use crate::{VOTE_ENTRY, VOTE_LINK_TYPE};
+
+pub fn vote_definition() -> ValidatingEntryType {
+    entry!(
+        name: VOTE_ENTRY,
+        description: "A vote on a poll message",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<Vote>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    if entry.option.is_some() {
+                        return Err("Votes must be cast by updating the voter's ballot".into());
+                    }
+                    load_poll(&entry, &validation_data.sources()).map(|_| ())
+                },
+                EntryValidationData::Modify{new_entry, old_entry, validation_data, ..} => {
+                    if new_entry.poll != old_entry.poll || new_entry.voter != old_entry.voter {
+                        return Err("A vote can only be changed to another option on the same poll".into());
+                    }
+                    validate_vote(&new_entry, &validation_data.sources(), header_time(&validation_data)?)
+                },
+                _ => Err("Cannot delete a vote".into()),
+            }
+        },
+
+        links: [
+            from!(
+                "message",
+                link_type: VOTE_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                // only the voter can link their ballot from the poll it is for
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources) = link_and_sources(validation_data);
+                    let vote: Vote = get_as_type(link.target().clone())
+                        .map_err(|_| String::from("Vote could not be found"))?;
+                    let ballot_address = Vote::ballot_address(link.base(), &vote.voter)
+                        .map_err(|e| e.to_string())?;
+                    if link.target() == &ballot_address && sources.contains(&vote.voter) {
+                        Ok(())
+                    } else {
+                        Err("Only the voter's ballot can be linked from their poll, by the voter".into())
+                    }
+                }
+            )
+        ]
+    )
+}
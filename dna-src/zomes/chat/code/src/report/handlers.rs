@@ -0,0 +1,109 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+    AGENT_ADDRESS,
+};
+use std::collections::HashSet;
+use crate::conversation::handlers::{ban_member, remove_message, require_moderator};
+use crate::report::{
+    QueuedReport, Report, ReportAction, ReportReason, ReportResolution, ReportSubject,
+};
+use crate::utils::{entry_created_at, get_links_and_load_type};
+use crate::{REPORT_ENTRY, REPORT_LINK_TYPE, REPORT_RESOLUTION_ENTRY, RESOLUTION_LINK_TYPE};
+
+/// File a report in the queue of the conversation, which its moderators read
+pub fn handle_report(
+    conversation_address: Address,
+    subject: ReportSubject,
+    reason: ReportReason,
+    note: String,
+) -> ZomeApiResult<Address> {
+    let report = Report {
+        conversation_address: conversation_address.clone(),
+        subject,
+        reason,
+        note,
+        reporter: AGENT_ADDRESS.to_string().into(),
+    };
+    let report_address = hdk::commit_entry(&Entry::App(REPORT_ENTRY.into(), report.into()))?;
+    hdk::link_entries(&conversation_address, &report_address, REPORT_LINK_TYPE, "")?;
+    Ok(report_address)
+}
+
+/// The first resolution recorded for a report going by their headers, if any
+fn get_resolution(report_address: &Address) -> ZomeApiResult<Option<ReportResolution>> {
+    let mut resolutions = get_links_and_load_type::<ReportResolution>(
+        report_address,
+        LinkMatch::Exactly(RESOLUTION_LINK_TYPE),
+        LinkMatch::Any,
+    )?
+    .into_iter()
+    .map(|result| Ok((entry_created_at(&result.address)?, result.entry)))
+    .collect::<ZomeApiResult<Vec<(Option<u64>, ReportResolution)>>>()?;
+    // resolutions whose header can't be found sort last
+    resolutions.sort_by_key(|(resolved_at, _)| resolved_at.unwrap_or(u64::max_value()));
+    Ok(resolutions.into_iter().next().map(|(_, resolution)| resolution))
+}
+
+/// Reports filed for a conversation this agent moderates, oldest first
+pub fn handle_get_reports(conversation_address: Address) -> ZomeApiResult<Vec<QueuedReport>> {
+    require_moderator(&conversation_address)?;
+    let mut reports = get_links_and_load_type::<Report>(
+        &conversation_address,
+        LinkMatch::Exactly(REPORT_LINK_TYPE),
+        LinkMatch::Any,
+    )?;
+    let mut uniques = HashSet::new();
+    reports.retain(|result| uniques.insert(result.address.clone()));
+    let mut reports = reports
+        .into_iter()
+        .map(|result| {
+            Ok(QueuedReport {
+                filed_at: entry_created_at(&result.address)?,
+                resolution: get_resolution(&result.address)?,
+                address: result.address,
+                report: result.entry,
+            })
+        })
+        .collect::<ZomeApiResult<Vec<QueuedReport>>>()?;
+    reports.sort_by_key(|queued| queued.filed_at);
+    Ok(reports)
+}
+
+/// Act on a report and record the outcome so it can be audited later
+pub fn handle_resolve_report(
+    report_address: Address,
+    action: ReportAction,
+    note: String,
+) -> ZomeApiResult<Address> {
+    let report: Report = get_as_type(report_address.clone())?;
+    require_moderator(&report.conversation_address)?;
+    if get_resolution(&report_address)?.is_some() {
+        return Err(ZomeApiError::Internal("Report has already been resolved".into()));
+    }
+    match (&action, &report.subject) {
+        (ReportAction::Dismiss, _) => (),
+        (ReportAction::DeleteMessage, ReportSubject::Message(message_address)) => {
+            remove_message(&report.conversation_address, message_address)?;
+        },
+        (ReportAction::DeleteMessage, ReportSubject::Profile(_)) => {
+            return Err(ZomeApiError::Internal("Only reported messages can be deleted".into()));
+        },
+        (ReportAction::BanAuthor, _) => {
+            let agent_address = report.reported_agent().map_err(ZomeApiError::Internal)?;
+            ban_member(&report.conversation_address, &agent_address)?;
+        },
+    }
+    let resolution = ReportResolution {
+        report: report_address.clone(),
+        action,
+        moderator: AGENT_ADDRESS.to_string().into(),
+        note,
+    };
+    let resolution_address =
+        hdk::commit_entry(&Entry::App(REPORT_RESOLUTION_ENTRY.into(), resolution.into()))?;
+    hdk::link_entries(&report_address, &resolution_address, RESOLUTION_LINK_TYPE, "")?;
+    Ok(resolution_address)
+}
@@ -0,0 +1,233 @@
+use hdk::{
+    self,
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::{
+        dna::entry_types::Sharing, entry::Entry, link::LinkMatch, validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+
+use crate::conversation::{validate_moderator, Conversation};
+use crate::message::Message;
+use crate::utils::{header_time, link_and_sources};
+
+pub mod handlers;
+
+pub const MAX_NOTE_LENGTH: usize = 1024;
+
+/// What is being reported
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportSubject {
+    Message(Address),
+    // the agent whose profile is reported
+    Profile(Address),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    Inappropriate,
+    Other,
+}
+
+/// A report filed with the moderators of a conversation. When it was filed is taken
+/// from its header rather than from the reporter.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct Report {
+    pub conversation_address: Address,
+    pub subject: ReportSubject,
+    pub reason: ReportReason,
+    pub note: String,
+    pub reporter: Address,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportAction {
+    Dismiss,
+    // take the reported message out of the conversation
+    DeleteMessage,
+    // ban the author of the reported message, or the reported agent
+    BanAuthor,
+}
+
+/// The outcome of a report, recorded by the moderator who resolved it
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct ReportResolution {
+    pub report: Address,
+    pub action: ReportAction,
+    pub moderator: Address,
+    pub note: String,
+}
+
+/// A report in a moderator's queue, along with how it was resolved if it has been
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct QueuedReport {
+    pub address: Address,
+    pub report: Report,
+    // when the report was filed going by its header
+    pub filed_at: Option<u64>,
+    pub resolution: Option<ReportResolution>,
+}
+
+impl Report {
+    /// The agent a report is about, which is who gets banned
+    pub fn reported_agent(&self) -> Result<Address, String> {
+        match &self.subject {
+            ReportSubject::Message(message_address) => {
+                let message: Message = get_as_type(message_address.clone())
+                    .map_err(|_| String::from("Reported message could not be found"))?;
+                Ok(message.author.into())
+            },
+            ReportSubject::Profile(agent_address) => Ok(agent_address.clone()),
+        }
+    }
+}
+
+fn validate_report(report: &Report, sources: &[Address]) -> Result<(), String> {
+    if !sources.contains(&report.reporter) {
+        return Err("Agents can only file reports as themselves".into());
+    }
+    if report.note.chars().count() > MAX_NOTE_LENGTH {
+        return Err(format!("Report notes can have at most {} characters", MAX_NOTE_LENGTH));
+    }
+    get_as_type::<Conversation>(report.conversation_address.clone())
+        .map_err(|_| String::from("Conversation could not be found"))?;
+    match &report.subject {
+        ReportSubject::Message(message_address) => {
+            report.reported_agent()?;
+            let posted_in_conversation = hdk::get_links(
+                &report.conversation_address,
+                LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+                LinkMatch::Any,
+            )
+            .map_err(|_| String::from("Could not load the conversation's messages"))?
+            .addresses()
+            .contains(message_address);
+            if posted_in_conversation {
+                Ok(())
+            } else {
+                Err("Reported message was not posted in the given conversation".into())
+            }
+        },
+        ReportSubject::Profile(agent_address) => match hdk::get_entry(agent_address) {
+            Ok(Some(Entry::AgentId(_))) => Ok(()),
+            _ => Err("Reported address is not a known agent".into()),
+        },
+    }
+}
+
+fn validate_resolution(resolution: &ReportResolution, sources: &[Address], at: u64) -> Result<(), String> {
+    if !sources.contains(&resolution.moderator) {
+        return Err("Reports can only be resolved as oneself".into());
+    }
+    if resolution.note.chars().count() > MAX_NOTE_LENGTH {
+        return Err(format!("Resolution notes can have at most {} characters", MAX_NOTE_LENGTH));
+    }
+    let report: Report = get_as_type(resolution.report.clone())
+        .map_err(|_| String::from("Report could not be found"))?;
+    if resolution.action == ReportAction::DeleteMessage {
+        if let ReportSubject::Profile(_) = report.subject {
+            return Err("Only reported messages can be deleted".into());
+        }
+    }
+    validate_moderator(&report.conversation_address, &[resolution.moderator.clone()], at)
+}
+
+use crate::{
+    MESSAGE_LINK_TYPE_TO, REPORT_ENTRY, REPORT_LINK_TYPE, REPORT_RESOLUTION_ENTRY,
+    RESOLUTION_LINK_TYPE,
+};
+
+pub fn report_definition() -> ValidatingEntryType {
+    entry!(
+        name: REPORT_ENTRY,
+        description: "A report of a message or profile to the moderators of a conversation",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<Report>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    validate_report(&entry, &validation_data.sources())
+                },
+                _ => Err("Cannot change or delete a report".into()),
+            }
+        },
+
+        links: [
+            from!(
+                "public_conversation",
+                link_type: REPORT_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                // reports are queued on the reported conversation by the reporter, and whoever
+                // moderates it when the queue is read gets to see them
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources) = link_and_sources(validation_data);
+                    let report: Report = get_as_type(link.target().clone())
+                        .map_err(|_| String::from("Report could not be found"))?;
+                    if &report.conversation_address == link.base() && sources.contains(&report.reporter) {
+                        Ok(())
+                    } else {
+                        Err("Reports can only be queued on their conversation by the reporter".into())
+                    }
+                }
+            )
+        ]
+    )
+}
+
+pub fn report_resolution_definition() -> ValidatingEntryType {
+    entry!(
+        name: REPORT_RESOLUTION_ENTRY,
+        description: "How a moderator resolved a report",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<ReportResolution>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    validate_resolution(&entry, &validation_data.sources(), header_time(&validation_data)?)
+                },
+                _ => Err("Cannot change or delete a report resolution".into()),
+            }
+        },
+
+        links: [
+            from!(
+                "report",
+                link_type: RESOLUTION_LINK_TYPE,
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |validation_data: hdk::LinkValidationData| {
+                    let (link, sources) = link_and_sources(validation_data);
+                    let resolution: ReportResolution = get_as_type(link.target().clone())
+                        .map_err(|_| String::from("Resolution could not be found"))?;
+                    if &resolution.report == link.base() && sources.contains(&resolution.moderator) {
+                        Ok(())
+                    } else {
+                        Err("Resolutions can only be linked from their report by the moderator".into())
+                    }
+                }
+            )
+        ]
+    )
+}
@@ -0,0 +1,86 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::entry::Entry,
+    holochain_persistence_api::cas::content::Address,
+};
+use std::collections::HashSet;
+use crate::conversation::handlers::handle_post_message;
+use crate::message::MessageSpec;
+use crate::schedule::{ScheduleOutcome, ScheduledMessage, ScheduledMessageDone};
+use crate::utils::{query_local_entries, GetLinksLoadResult};
+use crate::{SCHEDULED_MESSAGE_DONE_ENTRY, SCHEDULED_MESSAGE_ENTRY};
+
+fn mark_done(scheduled: Address, outcome: ScheduleOutcome) -> ZomeApiResult<Address> {
+    let done = ScheduledMessageDone { scheduled, outcome };
+    hdk::commit_entry(&Entry::App(SCHEDULED_MESSAGE_DONE_ENTRY.into(), done.into()))
+}
+
+pub fn handle_schedule_message(
+    conversation_address: Address,
+    spec: MessageSpec,
+    send_at: u64,
+) -> ZomeApiResult<Address> {
+    let nonce = query_local_entries::<ScheduledMessage>(SCHEDULED_MESSAGE_ENTRY)?.len() as u64;
+    let scheduled = ScheduledMessage {
+        conversation_address,
+        spec,
+        send_at,
+        nonce,
+    };
+    hdk::commit_entry(&Entry::App(SCHEDULED_MESSAGE_ENTRY.into(), scheduled.into()))
+}
+
+/// Scheduled messages that have not been sent, cancelled or failed, soonest first
+pub fn handle_list_scheduled_messages() -> ZomeApiResult<Vec<GetLinksLoadResult<ScheduledMessage>>> {
+    let done: HashSet<Address> = query_local_entries::<ScheduledMessageDone>(SCHEDULED_MESSAGE_DONE_ENTRY)?
+        .into_iter()
+        .map(|result| result.entry.scheduled)
+        .collect();
+    let mut pending: Vec<GetLinksLoadResult<ScheduledMessage>> =
+        query_local_entries::<ScheduledMessage>(SCHEDULED_MESSAGE_ENTRY)?
+            .into_iter()
+            .filter(|result| !done.contains(&result.address))
+            .collect();
+    let mut uniques = HashSet::new();
+    pending.retain(|e| uniques.insert(e.address.clone()));
+    pending.sort_by_key(|result| result.entry.send_at);
+    Ok(pending)
+}
+
+pub fn handle_cancel_scheduled_message(address: Address) -> ZomeApiResult<()> {
+    if !handle_list_scheduled_messages()?.iter().any(|result| result.address == address) {
+        return Err(ZomeApiError::Internal(
+            "No pending scheduled message at this address".into(),
+        ));
+    }
+    mark_done(address, ScheduleOutcome::Cancelled)?;
+    Ok(())
+}
+
+/// Post every scheduled message that is due as of `now`, timestamped with the time it was
+/// scheduled for. The client calls this periodically.
+/// A message that fails validation will never be accepted so it is marked failed. Any other
+/// error, such as a network failure or slow mode, leaves it pending to be retried on the next call.
+/// Returns the addresses of the scheduled messages that were posted.
+pub fn handle_send_scheduled_messages(now: u64) -> ZomeApiResult<Vec<Address>> {
+    let mut sent = Vec::new();
+    for result in handle_list_scheduled_messages()?
+        .into_iter()
+        .filter(|result| result.entry.send_at <= now)
+    {
+        let scheduled = result.entry;
+        let mut spec = scheduled.spec;
+        spec.timestamp = scheduled.send_at;
+        match handle_post_message(scheduled.conversation_address, spec) {
+            Ok(()) => {
+                mark_done(result.address.clone(), ScheduleOutcome::Sent)?;
+                sent.push(result.address);
+            }
+            Err(ZomeApiError::ValidationFailed(reason)) => {
+                mark_done(result.address, ScheduleOutcome::Failed(reason))?;
+            }
+            Err(_) => (),
+        }
+    }
+    Ok(sent)
+}
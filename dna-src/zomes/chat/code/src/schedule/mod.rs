@@ -0,0 +1,71 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::dna::entry_types::Sharing,
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+use crate::message::MessageSpec;
+
+pub mod handlers;
+
+/// A message waiting on the author's chain to be posted once `send_at` has passed
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct ScheduledMessage {
+    pub conversation_address: Address,
+    pub spec: MessageSpec,
+    pub send_at: u64,
+    // counts the agent's scheduled messages so scheduling the same message twice
+    // gives two entries rather than one
+    #[serde(default)]
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ScheduleOutcome {
+    Sent,
+    Cancelled,
+    Failed(String),
+}
+
+/// Records that a scheduled message is no longer pending. Private entries can't be
+/// removed from the chain so this is how a scheduled message is taken off the list.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct ScheduledMessageDone {
+    pub scheduled: Address,
+    pub outcome: ScheduleOutcome,
+}
+
+use crate::{SCHEDULED_MESSAGE_DONE_ENTRY, SCHEDULED_MESSAGE_ENTRY};
+
+pub fn scheduled_message_definition() -> ValidatingEntryType {
+    entry!(
+        name: SCHEDULED_MESSAGE_ENTRY,
+        description: "A message to be posted at a later time",
+        sharing: Sharing::Private,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |_validation_data: hdk::EntryValidationData<ScheduledMessage>| {
+            Ok(())
+        }
+    )
+}
+
+pub fn scheduled_message_done_definition() -> ValidatingEntryType {
+    entry!(
+        name: SCHEDULED_MESSAGE_DONE_ENTRY,
+        description: "Marks a scheduled message as sent, cancelled or failed",
+        sharing: Sharing::Private,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |_validation_data: hdk::EntryValidationData<ScheduledMessageDone>| {
+            Ok(())
+        }
+    )
+}
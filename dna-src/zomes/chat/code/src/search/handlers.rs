@@ -0,0 +1,134 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_persistence_api::cas::content::Address,
+    utils::get_as_type,
+};
+use std::collections::HashSet;
+use crate::activity::handlers::get_activity_summary;
+use crate::conversation::handlers::{handle_get_all_public_conversations, handle_get_members};
+use crate::message::Message;
+use crate::search::{conversation_matches, tokenize, ConversationSearchResult, ConversationSort, SearchIndex};
+use crate::utils::{entry_created_at, query_local_entries, GetLinksLoadResult};
+use crate::{MESSAGE_LINK_TYPE_TO, SEARCH_INDEX_ENTRY};
+
+/// Every index batch this agent has built for the conversation
+fn index_batches(conversation_address: &Address) -> ZomeApiResult<Vec<SearchIndex>> {
+    Ok(query_local_entries::<SearchIndex>(SEARCH_INDEX_ENTRY)?
+        .into_iter()
+        .map(|result| result.entry)
+        .filter(|index| &index.conversation_address == conversation_address)
+        .collect())
+}
+
+fn indexed_addresses(batches: &[SearchIndex]) -> HashSet<Address> {
+    batches.iter().flat_map(|index| index.indexed.iter().cloned()).collect()
+}
+
+fn load_message(address: Address) -> Option<GetLinksLoadResult<Message>> {
+    get_as_type::<Message>(address.clone())
+        .ok()
+        .map(|entry| GetLinksLoadResult { entry, address })
+}
+
+/// Index any messages in the conversation that are not yet in this agent's local index.
+/// Only the new messages are committed, as one batch. Returns `None` if there were none.
+pub fn handle_update_search_index(conversation_address: Address) -> ZomeApiResult<Option<Address>> {
+    let already_indexed = indexed_addresses(&index_batches(&conversation_address)?);
+    let mut index = SearchIndex::new(conversation_address.clone());
+
+    hdk::get_links(
+        &conversation_address,
+        LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+        LinkMatch::Any,
+    )?
+    .addresses()
+    .into_iter()
+    .filter(|address| !already_indexed.contains(address))
+    .filter_map(load_message)
+    .for_each(|result| index.insert(result.address, &result.entry.payload));
+
+    if index.indexed.is_empty() {
+        return Ok(None);
+    }
+    hdk::commit_entry(&Entry::App(SEARCH_INDEX_ENTRY.into(), index.into())).map(Some)
+}
+
+/// Find messages in a conversation whose payload contains every term of the query, newest first.
+/// Messages covered by the local index are looked up there, the rest are loaded and scanned.
+pub fn handle_search_messages(
+    conversation_address: Address,
+    query: String,
+    limit: usize,
+) -> ZomeApiResult<Vec<GetLinksLoadResult<Message>>> {
+    let terms = tokenize(&query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let batches = index_batches(&conversation_address)?;
+    let already_indexed = indexed_addresses(&batches);
+    let index_matches: HashSet<Address> = batches
+        .iter()
+        .flat_map(|index| index.matching(&terms))
+        .collect();
+
+    let mut result: Vec<GetLinksLoadResult<Message>> = hdk::get_links(
+        &conversation_address,
+        LinkMatch::Exactly(MESSAGE_LINK_TYPE_TO),
+        LinkMatch::Any,
+    )?
+    .addresses()
+    .into_iter()
+    .filter(|address| !already_indexed.contains(address) || index_matches.contains(address))
+    .filter_map(load_message)
+    .filter(|result| {
+        already_indexed.contains(&result.address) || tokenize(&result.entry.payload).is_superset(&terms)
+    })
+    .collect();
+
+    let mut uniques = HashSet::new();
+    result.retain(|e| uniques.insert(e.address.clone()));
+    result.sort_by(|a, b| b.entry.timestamp.cmp(&a.entry.timestamp));
+    result.truncate(limit);
+    Ok(result)
+}
+
+/// Public conversations of a community, or outside any community, whose name, description
+/// or tags match every term of the query, largest first by the chosen measure.
+/// An empty query matches every conversation.
+pub fn handle_search_conversations(
+    query: String,
+    sort: ConversationSort,
+    limit: usize,
+    community: Option<Address>,
+) -> ZomeApiResult<Vec<ConversationSearchResult>> {
+    let terms = tokenize(&query);
+    let mut result = handle_get_all_public_conversations(community)?
+        .into_iter()
+        .filter(|conversation| conversation_matches(&conversation.entry, &terms))
+        .map(|conversation| {
+            let members: HashSet<Address> =
+                handle_get_members(conversation.address.clone(), None)?.into_iter().collect();
+            let activity = get_activity_summary(&conversation.address)?;
+            Ok(ConversationSearchResult {
+                address: conversation.address,
+                conversation: conversation.entry,
+                member_count: members.len(),
+                message_count: activity.message_count,
+                last_activity: activity.last_activity,
+                created_at: entry_created_at(&conversation.address)?,
+            })
+        })
+        .collect::<ZomeApiResult<Vec<ConversationSearchResult>>>()?;
+
+    match sort {
+        ConversationSort::MemberCount => result.sort_by(|a, b| b.member_count.cmp(&a.member_count)),
+        ConversationSort::RecentActivity => result.sort_by(|a, b| b.last_activity.cmp(&a.last_activity)),
+        ConversationSort::CreationTime => {
+            result.sort_by(|a, b| b.created_at.cmp(&a.created_at))
+        }
+    }
+    result.truncate(limit);
+    Ok(result)
+}
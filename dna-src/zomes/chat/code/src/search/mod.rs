@@ -0,0 +1,116 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    holochain_core_types::dna::entry_types::Sharing,
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::conversation::Conversation;
+
+pub mod handlers;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationSort {
+    MemberCount,
+    RecentActivity,
+    CreationTime,
+}
+
+/// A conversation found by `search_conversations`, with the figures it was sorted by
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct ConversationSearchResult {
+    pub address: Address,
+    pub conversation: Conversation,
+    pub member_count: usize,
+    pub message_count: u64,
+    pub last_activity: Option<u64>,
+    // when the conversation was committed going by its header
+    pub created_at: Option<u64>,
+}
+
+/// Whether every query term starts one of the words of the conversation's name,
+/// description or tags
+pub fn conversation_matches(conversation: &Conversation, terms: &BTreeSet<String>) -> bool {
+    let mut words = tokenize(&conversation.name);
+    words.extend(tokenize(&conversation.description));
+    words.extend(conversation.tags.iter().flat_map(|tag| tokenize(tag)));
+    terms.iter().all(|term| words.iter().any(|word| word.starts_with(term.as_str())))
+}
+
+/// An inverted index over a batch of messages of one conversation. It is committed privately
+/// and only ever read by the agent that built it. Each batch covers only messages that no
+/// earlier batch for the conversation indexed, so together they form the whole index.
+#[derive(Serialize, Deserialize, Debug, Clone, DefaultJson)]
+pub struct SearchIndex {
+    pub conversation_address: Address,
+    pub indexed: Vec<Address>,
+    // term -> positions in `indexed` of the messages containing it
+    pub terms: BTreeMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+    pub fn new(conversation_address: Address) -> SearchIndex {
+        SearchIndex {
+            conversation_address,
+            indexed: Vec::new(),
+            terms: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, message_address: Address, payload: &str) {
+        let position = self.indexed.len();
+        self.indexed.push(message_address);
+        for term in tokenize(payload) {
+            self.terms.entry(term).or_insert_with(Vec::new).push(position);
+        }
+    }
+
+    /// Addresses of indexed messages containing every one of the given terms
+    pub fn matching(&self, terms: &BTreeSet<String>) -> Vec<Address> {
+        let mut positions: Option<BTreeSet<usize>> = None;
+        for term in terms {
+            let term_positions: BTreeSet<usize> = self
+                .terms
+                .get(term)
+                .map(|p| p.iter().cloned().collect())
+                .unwrap_or_default();
+            positions = Some(match positions {
+                Some(current) => current.intersection(&term_positions).cloned().collect(),
+                None => term_positions,
+            });
+        }
+        positions
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|position| self.indexed.get(position).cloned())
+            .collect()
+    }
+}
+
+/// Split text into lowercase alphanumeric terms
+pub fn tokenize(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+use crate::SEARCH_INDEX_ENTRY;
+
+pub fn search_index_definition() -> ValidatingEntryType {
+    entry!(
+        name: SEARCH_INDEX_ENTRY,
+        description: "A local index used to speed up searching the messages of a conversation",
+        sharing: Sharing::Private,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |_validation_data: hdk::EntryValidationData<SearchIndex>| {
+            Ok(())
+        }
+    )
+}
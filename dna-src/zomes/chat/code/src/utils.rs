@@ -1,18 +1,23 @@
 use hdk::{
     error::{ZomeApiError, ZomeApiResult},
     holochain_core_types::{
+        chain_header::ChainHeader,
         entry::{AppEntryValue, Entry},
-        link::LinkMatch,
+        link::{Link, LinkMatch},
+        validation::ValidationData,
     },
     holochain_json_api::{
         json::{default_to_json, JsonString},
     },
     holochain_persistence_api::{
-        cas::content::{ Address, AddressableContent },
+        cas::content::{Address, AddressableContent},
     },
+    holochain_wasm_utils::api_serialization::get_entry::{GetEntryOptions, GetEntryResultType, StatusRequestKind},
+    prelude::{QueryArgsOptions, QueryResult},
+    LinkValidationData,
 };
 use serde::Serialize;
-use std::{convert::TryFrom, fmt::Debug};
+use std::{collections::HashMap, convert::TryFrom, fmt::Debug};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GetLinksLoadResult<T> {
@@ -31,29 +36,27 @@ impl<T: Into<JsonString> + Debug + Serialize> From<GetLinksLoadResult<T>> for Js
 /// of a get_links_and_load for a given type. Any entries that either fail to
 /// load or cannot be converted to the type will be dropped.
 ///
+/// The address returned is always the link target. Once an entry has been updated
+/// the loaded content is the latest version, whose own address has no links on it.
+///
 pub fn get_links_and_load_type<R: TryFrom<AppEntryValue>>(
     base: &Address,
     link_type: LinkMatch<&str>,
     tag: LinkMatch<&str>,
 ) -> ZomeApiResult<Vec<GetLinksLoadResult<R>>> {
-    let link_load_results = hdk::get_links_and_load(base, link_type, tag)?;
+    let link_targets = hdk::get_links(base, link_type, tag)?.addresses();
 
-    Ok(link_load_results
-        .iter()
-        .map(|maybe_entry| match maybe_entry {
-            Ok(entry) => match entry {
-                Entry::App(_, entry_value) => {
-                    let typed_entry = R::try_from(entry_value.to_owned()).map_err(|_| {
-                        ZomeApiError::Internal(
-                            "Could not convert get_links result to requested type".to_string(),
-                        )
-                    })?;
-                    Ok((entry.address(), typed_entry))
-                }
-                _ => Err(ZomeApiError::Internal(
-                    "get_links did not return an app entry".to_string(),
-                )),
-            },
+    Ok(link_targets
+        .into_iter()
+        .map(|address| match hdk::get_entry(&address) {
+            Ok(Some(Entry::App(_, entry_value))) => {
+                let typed_entry = R::try_from(entry_value).map_err(|_| {
+                    ZomeApiError::Internal(
+                        "Could not convert get_links result to requested type".to_string(),
+                    )
+                })?;
+                Ok((address, typed_entry))
+            }
             _ => Err(ZomeApiError::Internal(
                 "get_links did not return an app entry".to_string(),
             )),
@@ -62,3 +65,272 @@ pub fn get_links_and_load_type<R: TryFrom<AppEntryValue>>(
         .map(|(address, entry)| GetLinksLoadResult { address, entry })
         .collect())
 }
+
+///
+/// Helper function that loads every entry of the given app entry type from the
+/// local source chain, oldest first. Any entries that cannot be converted to the
+/// type will be dropped.
+///
+pub fn query_local_entries<R: TryFrom<AppEntryValue>>(
+    entry_type: &str,
+) -> ZomeApiResult<Vec<GetLinksLoadResult<R>>> {
+    if let QueryResult::Entries(results) = hdk::query_result(
+        entry_type.into(),
+        QueryArgsOptions{ entries: true, ..Default::default()}
+    )? {
+        Ok(results
+            .into_iter()
+            .filter_map(|(address, entry)| match entry {
+                Entry::App(_, entry_value) => R::try_from(entry_value)
+                    .ok()
+                    .map(|entry| GetLinksLoadResult { address, entry }),
+                _ => None,
+            })
+            .collect())
+    } else {
+        unreachable!()
+    }
+}
+
+///
+/// Helper function that loads every link this agent has added or removed on their own chain,
+/// along with the header recording when it was added or removed, oldest first.
+///
+pub fn query_local_links() -> ZomeApiResult<Vec<(ChainHeader, Entry)>> {
+    if let QueryResult::HeadersWithEntries(results) = hdk::query_result(
+        vec!["%link_add", "%link_remove"].into(),
+        QueryArgsOptions{ entries: true, headers: true, ..Default::default()}
+    )? {
+        Ok(results)
+    } else {
+        unreachable!()
+    }
+}
+
+///
+/// Helper function for validation callbacks with a `ChainFull` validation package that pairs
+/// every entry on the author's chain with its header.
+///
+pub fn author_chain(validation_data: &ValidationData) -> Vec<(ChainHeader, Entry)> {
+    let package = &validation_data.package;
+    let entries: HashMap<Address, &Entry> = package
+        .source_chain_entries
+        .iter()
+        .flatten()
+        .map(|entry| (entry.address(), entry))
+        .collect();
+    package
+        .source_chain_headers
+        .iter()
+        .flatten()
+        .filter_map(|header| {
+            entries
+                .get(header.entry_address())
+                .map(|entry| (header.clone(), (*entry).clone()))
+        })
+        .collect()
+}
+
+///
+/// Helper function for link validation callbacks that returns the link along with
+/// the agents that authored it, whether the link is being added or removed.
+///
+pub fn link_and_sources(validation_data: LinkValidationData) -> (Link, Vec<Address>) {
+    match validation_data {
+        LinkValidationData::LinkAdd { link, validation_data } => (link.link, validation_data.sources()),
+        LinkValidationData::LinkRemove { link, validation_data } => (link.link, validation_data.sources()),
+    }
+}
+
+///
+/// Like `link_and_sources`, also returning when the link was added or removed going by its header.
+///
+pub fn link_sources_and_time(validation_data: LinkValidationData) -> Result<(Link, Vec<Address>, u64), String> {
+    let (link, validation_data) = match validation_data {
+        LinkValidationData::LinkAdd { link, validation_data } => (link.link, validation_data),
+        LinkValidationData::LinkRemove { link, validation_data } => (link.link, validation_data),
+    };
+    Ok((link, validation_data.sources(), header_time(&validation_data)?))
+}
+
+///
+/// Helper function that loads the version of an entry that was current at the given time,
+/// going by the headers of its versions. Returns `None` if the entry did not exist yet.
+/// Validation checks against this rather than the latest version so that whether something
+/// was allowed doesn't change when the entry is updated afterwards.
+///
+pub fn entry_at<R: TryFrom<AppEntryValue>>(address: &Address, at: u64) -> ZomeApiResult<Option<R>> {
+    let options = GetEntryOptions {
+        status_request: StatusRequestKind::All,
+        entry: true,
+        headers: true,
+        ..Default::default()
+    };
+    let history = match hdk::get_entry_result(address, options)?.result {
+        GetEntryResultType::All(history) => history,
+        _ => return Ok(None),
+    };
+    Ok(history
+        .items
+        .into_iter()
+        .filter_map(|item| {
+            let committed_at = item
+                .headers
+                .iter()
+                .filter_map(|header| parse_iso8601_millis(&header.timestamp().to_string()))
+                .min()?;
+            match item.entry? {
+                Entry::App(_, entry_value) => R::try_from(entry_value)
+                    .ok()
+                    .map(|entry| (committed_at, entry)),
+                _ => None,
+            }
+        })
+        .filter(|(committed_at, _)| *committed_at <= at)
+        .max_by_key(|(committed_at, _)| *committed_at)
+        .map(|(_, entry)| entry))
+}
+
+///
+/// Helper function that returns when an entry was first committed going by its header,
+/// or `None` if it cannot be found.
+///
+pub fn entry_created_at(address: &Address) -> ZomeApiResult<Option<u64>> {
+    let options = GetEntryOptions {
+        status_request: StatusRequestKind::Initial,
+        headers: true,
+        ..Default::default()
+    };
+    match hdk::get_entry_result(address, options)?.result {
+        GetEntryResultType::Single(item) => Ok(item
+            .headers
+            .iter()
+            .filter_map(|header| parse_iso8601_millis(&header.timestamp().to_string()))
+            .min()),
+        _ => Ok(None),
+    }
+}
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+///
+/// Milliseconds since the unix epoch of an RFC 3339 timestamp such as
+/// `2020-05-04T10:20:30.123+02:00`, or `None` if it cannot be read or is before the epoch.
+///
+pub fn parse_iso8601_millis(timestamp: &str) -> Option<u64> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = timestamp.get(range)?;
+        if digits.chars().all(|c| c.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    };
+    let separators = timestamp.as_bytes();
+    if separators.get(4) != Some(&b'-')
+        || separators.get(7) != Some(&b'-')
+        || !separators.get(10).map(|c| b"Tt ".contains(c)).unwrap_or(false)
+        || separators.get(13) != Some(&b':')
+        || separators.get(16) != Some(&b':')
+    {
+        return None;
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let rest = timestamp.get(19..)?;
+    let (fraction, offset) = rest.split_at(
+        rest.find(|c: char| c != '.' && !c.is_ascii_digit()).unwrap_or(rest.len()),
+    );
+    let millis = match fraction.as_bytes().first() {
+        None => 0,
+        Some(b'.') if fraction.len() > 1 && !fraction[1..].contains('.') => {
+            let digits: String = fraction[1..].chars().chain("00".chars()).take(3).collect();
+            digits.parse::<i64>().ok()?
+        }
+        _ => return None,
+    };
+    let offset_minutes = match offset {
+        "Z" | "z" => 0,
+        offset if offset.len() == 6 && offset.as_bytes()[3] == b':' => {
+            let sign = match offset.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let hours: i64 = offset.get(1..3)?.parse().ok()?;
+            let minutes: i64 = offset.get(4..6)?.parse().ok()?;
+            sign * (hours * 60 + minutes)
+        }
+        _ => return None,
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+        - offset_minutes * 60;
+    let total = seconds.checked_mul(1_000)?.checked_add(millis)?;
+    if total < 0 {
+        None
+    } else {
+        Some(total as u64)
+    }
+}
+
+///
+/// Helper function for validation callbacks that returns the time the entry or link being
+/// validated was committed, in milliseconds since the unix epoch, as recorded in its chain header.
+/// Unlike timestamps inside entries this cannot be chosen freely by the author.
+///
+pub fn header_time(validation_data: &ValidationData) -> Result<u64, String> {
+    let timestamp = validation_data.package.chain_header.timestamp().to_string();
+    parse_iso8601_millis(&timestamp)
+        .ok_or_else(|| format!("Could not read the header timestamp {}", timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_utc_timestamps() {
+        assert_eq!(parse_iso8601_millis("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_iso8601_millis("1970-01-01T00:00:00+00:00"), Some(0));
+        assert_eq!(parse_iso8601_millis("2020-02-29T12:34:56Z"), Some(1_582_979_696_000));
+        assert_eq!(parse_iso8601_millis("2000-01-01T00:00:00Z"), Some(946_684_800_000));
+    }
+
+    #[test]
+    fn parses_fractions_and_offsets() {
+        assert_eq!(parse_iso8601_millis("1970-01-01T00:00:01.5Z"), Some(1_500));
+        assert_eq!(parse_iso8601_millis("1970-01-01T00:00:01.123456789Z"), Some(1_123));
+        assert_eq!(parse_iso8601_millis("1970-01-01T02:00:00+02:00"), Some(0));
+        assert_eq!(parse_iso8601_millis("1969-12-31T23:00:00-01:00"), Some(0));
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        for timestamp in &[
+            "",
+            "2020",
+            "2020-13-01T00:00:00Z",
+            "2020-01-01 00:00:00",
+            "2020-01-01T00:00:00+0200",
+            "2020-01-01T00:00:00.Z",
+            "1969-12-31T23:59:59Z",
+            "20x0-01-01T00:00:00Z",
+            "2020-01-01T00:00:00Zjunk",
+        ] {
+            assert_eq!(parse_iso8601_millis(timestamp), None, "{}", timestamp);
+        }
+    }
+}